@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    debug_handler,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{
+    ChainClient, MintBatch, MintBatchRow, MintBatchRowStatus, MintingBatches, MintingGuard,
+    SignerData, Store, TGAS,
+};
+use errors::{APIResult, FieldError};
+use mint_cert::{MintPayloadDetails, MINT_DEPOSIT};
+use near_certification_tools::MintNFT;
+use near_primitives::{
+    errors::{ActionError, TxExecutionError},
+    transaction::{Action, FunctionCallAction},
+    views::FinalExecutionStatus,
+};
+
+/// Maximum number of certificates minted by a single transaction.
+const CHUNK_SIZE: usize = 10;
+/// Gas of every `nft_mint` in a chunk, keeping the transaction well under
+/// the 300 TGas limit.
+const MINT_GAS: u64 = 25 * TGAS;
+
+#[derive(serde::Serialize)]
+pub struct BatchView {
+    batch_id: String,
+    pending: usize,
+    succeeded: usize,
+    failed: usize,
+    rows: Vec<RowView>,
+}
+
+/// Result of a row, also a line of the CSV report.
+#[derive(serde::Serialize)]
+struct RowView {
+    row: usize,
+    token_id: String,
+    recipient_id: String,
+    status: &'static str,
+    message: Option<String>,
+}
+
+impl From<&MintBatch> for BatchView {
+    fn from(batch: &MintBatch) -> BatchView {
+        let rows: Vec<_> = batch
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let (status, message) = match &row.status {
+                    MintBatchRowStatus::Pending => ("pending", None),
+                    MintBatchRowStatus::Succeeded => ("succeeded", None),
+                    MintBatchRowStatus::Failed { message } => ("failed", Some(message.clone())),
+                };
+
+                RowView {
+                    row: index + 1,
+                    token_id: row.token_id.clone(),
+                    recipient_id: row.recipient_id.clone(),
+                    status,
+                    message,
+                }
+            })
+            .collect();
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+
+        BatchView {
+            batch_id: batch.batch_id.clone(),
+            pending: count("pending"),
+            succeeded: count("succeeded"),
+            failed: count("failed"),
+            rows,
+        }
+    }
+}
+
+/// Reads the rows of a CSV upload, with a header line naming the fields of
+/// `MintPayloadDetails`, or of a JSON array of `MintPayloadDetails`.
+fn parse_rows(headers: &HeaderMap, body: &[u8]) -> APIResult<Vec<MintNFT>> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok());
+    let is_csv = matches!(content_type, Some(content_type) if content_type.starts_with("text/csv"));
+
+    let rows: Vec<Result<MintPayloadDetails, String>> = if is_csv {
+        csv::Reader::from_reader(body)
+            .deserialize()
+            .map(|row| row.map_err(|error: csv::Error| error.to_string()))
+            .collect()
+    } else {
+        match serde_json::from_slice::<Vec<MintPayloadDetails>>(body) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(error) => {
+                return Err(errors::APIError::InvalidBatch {
+                    errors: vec![error.to_string()],
+                })
+            }
+        }
+    };
+
+    if rows.is_empty() {
+        return Err(errors::APIError::InvalidBatch {
+            errors: vec!["Batch has no rows".to_string()],
+        });
+    }
+
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|row| {
+            let details = row.map_err(|error| vec![error])?;
+
+            MintNFT::try_from(details).map_err(|error| match error {
+                errors::APIError::InvalidPayload { errors } => errors
+                    .into_iter()
+                    .map(|FieldError { field, message }| format!("{field}: {message}"))
+                    .collect(),
+                error => vec![error.to_string()],
+            })
+        })
+        .collect();
+
+    // Reject the whole batch rather than minting part of it
+    let errors: Vec<_> = rows
+        .iter()
+        .enumerate()
+        .filter_map(|(index, row)| Some((index, row.as_ref().err()?)))
+        .flat_map(|(index, errors)| {
+            errors
+                .iter()
+                .map(move |error| format!("row {}: {error}", index + 1))
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(errors::APIError::InvalidBatch { errors });
+    }
+
+    Ok(rows.into_iter().flatten().collect())
+}
+
+fn mint_action(row: &MintBatchRow) -> Action {
+    Action::FunctionCall(FunctionCallAction {
+        method_name: "nft_mint".to_string(),
+        args: row.mint_args.to_string().into_bytes(),
+        gas: MINT_GAS,
+        deposit: MINT_DEPOSIT,
+    })
+}
+
+/// Mints the pending rows of `batch` chunk by chunk, saving the results after
+/// every transaction.
+async fn mint_pending_rows(
+    chain_client: &dyn ChainClient,
+    signer_data: &SignerData,
+    mint_batches: &Store<MintBatch>,
+    mut batch: MintBatch,
+) -> APIResult<()> {
+    loop {
+        let chunk: Vec<_> = batch
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row.status, MintBatchRowStatus::Pending))
+            .map(|(index, _)| index)
+            .take(CHUNK_SIZE)
+            .collect();
+
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        let actions = chunk
+            .iter()
+            .map(|&index| mint_action(&batch.rows[index]))
+            .collect();
+
+        let failed_rows = match chain_client
+            .send_transaction_to_certs(actions, signer_data.clone())
+            .await
+        {
+            Ok(FinalExecutionStatus::SuccessValue(_)) => {
+                for &index in &chunk {
+                    batch.rows[index].status = MintBatchRowStatus::Succeeded;
+                }
+                vec![]
+            }
+            Ok(FinalExecutionStatus::Failure(error)) => {
+                let message = error.to_string();
+
+                match error {
+                    // The whole transaction is reverted, so only the row which
+                    // failed is given up and the others go with the next chunk
+                    TxExecutionError::ActionError(ActionError {
+                        index: Some(action_index),
+                        ..
+                    }) if (action_index as usize) < chunk.len() => {
+                        vec![(chunk[action_index as usize], message)]
+                    }
+                    _ => chunk
+                        .iter()
+                        .map(|&index| (index, message.clone()))
+                        .collect(),
+                }
+            }
+            Ok(_) => chunk
+                .iter()
+                .map(|&index| (index, "Transaction is not yet completed.".to_string()))
+                .collect(),
+            // The transaction may still go through, which a retry finds out
+            Err(error) => chunk
+                .iter()
+                .map(|&index| (index, error.to_string()))
+                .collect(),
+        };
+
+        for (index, message) in failed_rows {
+            batch.rows[index].status = MintBatchRowStatus::Failed { message };
+        }
+
+        mint_batches.insert(&batch.batch_id, batch.clone())?;
+    }
+}
+
+/// Mints the pending rows in the background, holding `guard` until done.
+fn spawn_minting(
+    chain_client: Arc<dyn ChainClient>,
+    signer_data: SignerData,
+    mint_batches: Arc<Store<MintBatch>>,
+    batch: MintBatch,
+    guard: MintingGuard,
+) {
+    tokio::spawn(async move {
+        if let Err(error) =
+            mint_pending_rows(chain_client.as_ref(), &signer_data, &mint_batches, batch).await
+        {
+            eprintln!("{error:?}");
+        }
+        drop(guard);
+    });
+}
+
+#[debug_handler(state = common::AppData)]
+pub async fn create_handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
+    State(signer_data): State<SignerData>,
+    State(mint_batches): State<Arc<Store<MintBatch>>>,
+    State(minting_batches): State<Arc<MintingBatches>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> APIResult<(StatusCode, Json<BatchView>)> {
+    let rows = parse_rows(&headers, &body)?
+        .into_iter()
+        .map(|mint_nft| MintBatchRow {
+            token_id: mint_nft.token_id.clone(),
+            recipient_id: mint_nft
+                .receiver_account_id
+                .as_ref()
+                .map_or_else(String::new, ToString::to_string),
+            mint_args: serde_json::json!(mint_nft),
+            status: MintBatchRowStatus::Pending,
+        })
+        .collect();
+
+    let batch = MintBatch {
+        batch_id: uuid::Uuid::new_v4().simple().to_string(),
+        rows,
+    };
+    let Some(guard) = minting_batches.start(&batch.batch_id) else {
+        return Err(errors::APIError::BatchInProgress {
+            batch_id: batch.batch_id,
+        });
+    };
+    mint_batches.insert(&batch.batch_id, batch.clone())?;
+
+    let view = BatchView::from(&batch);
+    spawn_minting(chain_client, signer_data, mint_batches, batch, guard);
+
+    Ok((StatusCode::ACCEPTED, Json(view)))
+}
+
+#[derive(serde::Deserialize)]
+pub struct BatchQuery {
+    /// `csv` to download the results as a CSV report.
+    format: Option<String>,
+}
+
+#[debug_handler(state = common::AppData)]
+pub async fn handler(
+    State(mint_batches): State<Arc<Store<MintBatch>>>,
+    Path(batch_id): Path<String>,
+    Query(BatchQuery { format }): Query<BatchQuery>,
+) -> APIResult<Response> {
+    let Some(batch) = mint_batches.get(&batch_id) else {
+        return Err(errors::APIError::BatchNotFound { batch_id });
+    };
+    let view = BatchView::from(&batch);
+
+    if format.as_deref() != Some("csv") {
+        return Ok(Json(view).into_response());
+    }
+
+    let mut report = csv::Writer::from_writer(vec![]);
+    for row in &view.rows {
+        if report.serialize(row).is_err() {
+            return Err(errors::APIError::ServerError);
+        }
+    }
+    let Ok(report) = report.into_inner() else {
+        return Err(errors::APIError::ServerError);
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"batch-{batch_id}.csv\""),
+            ),
+        ],
+        report,
+    )
+        .into_response())
+}
+
+/// Mints the rows of a batch which haven't succeeded, e.g. after a failed
+/// transaction. Rows whose certificate turns out to exist are marked as
+/// succeeded instead of being minted again. A batch still being minted is
+/// rejected, as both runs would save their own copy of the batch.
+#[debug_handler(state = common::AppData)]
+pub async fn retry_handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
+    State(signer_data): State<SignerData>,
+    State(mint_batches): State<Arc<Store<MintBatch>>>,
+    State(minting_batches): State<Arc<MintingBatches>>,
+    Path(batch_id): Path<String>,
+) -> APIResult<(StatusCode, Json<BatchView>)> {
+    let Some(mut batch) = mint_batches.get(&batch_id) else {
+        return Err(errors::APIError::BatchNotFound { batch_id });
+    };
+    let Some(guard) = minting_batches.start(&batch_id) else {
+        return Err(errors::APIError::BatchInProgress { batch_id });
+    };
+
+    for row in &mut batch.rows {
+        if matches!(row.status, MintBatchRowStatus::Succeeded) {
+            continue;
+        }
+
+        row.status = match common::find_token(chain_client.as_ref(), &row.token_id).await? {
+            Some(_) => MintBatchRowStatus::Succeeded,
+            None => MintBatchRowStatus::Pending,
+        };
+    }
+    mint_batches.insert(&batch_id, batch.clone())?;
+
+    let view = BatchView::from(&batch);
+    spawn_minting(chain_client, signer_data, mint_batches, batch, guard);
+
+    Ok((StatusCode::ACCEPTED, Json(view)))
+}
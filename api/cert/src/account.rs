@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    http::{header::ACCEPT_LANGUAGE, HeaderMap},
+    Json,
+};
+use common::ChainClient;
+use errors::APIResult;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use near_certification_tools::AccountId;
+
+use crate::{db::Database, locale, look_up, read_token, CertificateData};
+
+/// Maximum number of certificates listed at once, also the default.
+const MAX_LIMIT: usize = 100;
+/// Number of certificates looked up at the same time.
+const LOOK_UP_CONCURRENCY: usize = 10;
+
+#[derive(serde::Deserialize)]
+pub struct AccountCertificatesQuery {
+    /// Only list certificates of this program code.
+    program: Option<String>,
+    /// Leave out invalidated certificates.
+    #[serde(default)]
+    valid_only: bool,
+    /// Preferred language tag, overriding the `Accept-Language` header.
+    lang: Option<String>,
+    /// Number of matching certificates to skip.
+    #[serde(default)]
+    from_index: usize,
+    /// Maximum number of certificates to list, at most `MAX_LIMIT`.
+    limit: Option<usize>,
+}
+
+/// Certificates owned by an account, invalidated ones included unless
+/// `valid_only` is set, a page at a time. Tokens whose metadata can't be
+/// read are left out rather than failing the whole list.
+#[debug_handler]
+pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
+    Path(account_id): Path<String>,
+    Query(query): Query<AccountCertificatesQuery>,
+    headers: HeaderMap,
+) -> APIResult<Json<Vec<CertificateData>>> {
+    if account_id.parse::<AccountId>().is_err() {
+        return Err(errors::APIError::AccountInvalid { account_id });
+    }
+
+    let preferred_languages = locale::preferred_languages(
+        query.lang.as_deref(),
+        headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|accept_language| accept_language.to_str().ok()),
+    );
+
+    let certificates: Vec<_> = common::get_tokens_for_owner(chain_client.as_ref(), &account_id)
+        .await?
+        .into_iter()
+        .filter_map(|token| {
+            let token_id = token.token_id.clone();
+
+            match read_token(token, &preferred_languages) {
+                Ok((certificate, _)) => Some(certificate),
+                Err(error) => {
+                    eprintln!("Skipping token {token_id}: {error}");
+                    None
+                }
+            }
+        })
+        .filter(|certificate| {
+            let is_other_program =
+                matches!(&query.program, Some(program) if *program != certificate.program_code);
+
+            !is_other_program && (certificate.valid || !query.valid_only)
+        })
+        .skip(query.from_index)
+        .take(query.limit.unwrap_or(MAX_LIMIT).min(MAX_LIMIT))
+        .collect();
+
+    let database = Database::default();
+    let certificates = stream::iter(certificates)
+        .map(|mut certificate| {
+            let (chain_client, database) = (chain_client.as_ref(), &database);
+
+            async move {
+                look_up(chain_client, database, &mut certificate).await?;
+
+                Ok(certificate)
+            }
+        })
+        .buffered(LOOK_UP_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(Json(certificates))
+}
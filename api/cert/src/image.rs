@@ -0,0 +1,490 @@
+use std::{fmt::Write, sync::Arc};
+
+use axum::{
+    http::header::{CACHE_CONTROL, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use errors::APIResult;
+use once_cell::sync::Lazy;
+use resvg::{tiny_skia, usvg};
+
+use crate::{template, CertificateData};
+
+const WIDTH: u32 = 1080;
+const HEIGHT: u32 = 1080;
+
+const MANROPE: &[u8] = include_bytes!("../assets/fonts/Manrope-VariableFont_wght.ttf");
+const DM_MONO: &[u8] = include_bytes!("../assets/fonts/DMMono-Medium.ttf");
+
+// The fonts are parsed once for the lifetime of the server. `Lazy` stands in
+// for `std::sync::OnceLock`, which the minimum supported Rust version lacks.
+static MANROPE_FACE: Lazy<ttf_parser::Face<'static>> =
+    Lazy::new(|| ttf_parser::Face::parse(MANROPE, 0).expect("Bundled font is invalid"));
+static DM_MONO_FACE: Lazy<ttf_parser::Face<'static>> =
+    Lazy::new(|| ttf_parser::Face::parse(DM_MONO, 0).expect("Bundled font is invalid"));
+static FONT_DATABASE: Lazy<Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+    let mut database = usvg::fontdb::Database::new();
+    database.load_font_source(usvg::fontdb::Source::Binary(Arc::new(MANROPE)));
+    database.load_font_source(usvg::fontdb::Source::Binary(Arc::new(DM_MONO)));
+
+    Arc::new(database)
+});
+
+const GRAY: &str = "#757575";
+const BLACK: &str = "#000000";
+const BLUE: &str = "#5F8AFA";
+
+const BODY_WIDTH: f32 = 950.0;
+const LEFT_PADDING: f32 = 65.0;
+const X_CENTER: f32 = WIDTH as f32 / 2.0;
+const X_POSITION_OF_INSTRUCTOR: f32 = 210.0;
+const INSTRUCTOR_WIDTH: f32 = 540.0;
+const X_POSITION_OF_DATE: f32 = LEFT_PADDING + BODY_WIDTH;
+const X_POSITION_OF_DATE_LABEL: f32 = X_POSITION_OF_DATE - 190.0;
+const LINE_HEIGHT: f32 = 1.2;
+const EXPIRATION_EXPLANATION_LINE_LENGTH: usize = 110;
+
+/// Images only change when the expiration does, which is looked up in the
+/// explorer database, so they can be cached for a while.
+const CACHE_SECONDS: u32 = 6 * 60 * 60;
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    /// Splits a file name such as `{token_id}.svg` into the token ID and the
+    /// format of the image, `None` if it isn't the name of an image.
+    pub fn from_file_name(file_name: &str) -> Option<(&str, Format)> {
+        let (token_id, extension) = file_name.rsplit_once('.')?;
+        let format = match extension {
+            "svg" => Format::Svg,
+            "png" => Format::Png,
+            _ => return None,
+        };
+
+        Some((token_id, format))
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Svg => "image/svg+xml",
+            Format::Png => "image/png",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Typeface {
+    Manrope,
+    DmMono,
+}
+
+impl Typeface {
+    fn family(self) -> &'static str {
+        match self {
+            Typeface::Manrope => "Manrope",
+            Typeface::DmMono => "DM Mono",
+        }
+    }
+
+    fn face(self) -> &'static ttf_parser::Face<'static> {
+        match self {
+            Typeface::Manrope => &MANROPE_FACE,
+            Typeface::DmMono => &DM_MONO_FACE,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Font {
+    typeface: Typeface,
+    size: f32,
+    weight: u16,
+}
+
+impl Font {
+    const fn new(typeface: Typeface, size: f32, weight: u16) -> Self {
+        Font {
+            typeface,
+            size,
+            weight,
+        }
+    }
+
+    /// Width of `text` set in this font, in pixels.
+    fn measure(self, text: &str) -> f32 {
+        let face = self.typeface.face();
+        let advance: u32 = text
+            .chars()
+            .map(|c| {
+                let glyph_id = face.glyph_index(c).unwrap_or_default();
+                u32::from(face.glyph_hor_advance(glyph_id).unwrap_or_default())
+            })
+            .sum();
+
+        advance as f32 * self.size / f32::from(face.units_per_em())
+    }
+
+    /// Distance from the top of a line to its baseline.
+    fn ascent(self) -> f32 {
+        let face = self.typeface.face();
+
+        f32::from(face.ascender()) * self.size / f32::from(face.units_per_em())
+    }
+
+    /// Width of the outline which makes up for the difference between the
+    /// weight of the font and the weight of the bundled face. The text layout
+    /// doesn't support font variations, so the variable Manrope font is always
+    /// drawn at its lightest weight.
+    fn stroke_width(self) -> f32 {
+        let face_weight = self.typeface.face().weight().to_number();
+
+        f32::from(self.weight.saturating_sub(face_weight)) * self.size / 10_000.0
+    }
+}
+
+const TITLE_FONT: Font = Font::new(Typeface::Manrope, 64.0, 800);
+const DESCRIPTION_FONT: Font = Font::new(Typeface::Manrope, 33.0, 400);
+const FIELD_LABEL_FONT: Font = Font::new(Typeface::Manrope, 28.0, 800);
+const EXPIRATION_EXPLANATION_FONT: Font = Font::new(Typeface::Manrope, 20.0, 400);
+const PROGRAM_FONT: Font = Font::new(Typeface::DmMono, 40.0, 500);
+const DATE_FONT: Font = Font::new(Typeface::DmMono, 30.0, 500);
+const TOKEN_ID_FONT: Font = Font::new(Typeface::DmMono, 30.0, 500);
+const ACCOUNT_NAME_FONT: Font = Font::new(Typeface::DmMono, 60.0, 500);
+const INSTRUCTOR_FONT: Font = Font::new(Typeface::DmMono, 30.0, 500);
+
+#[derive(Clone, Copy)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Text elements drawn over the background of a template, positioned like on
+/// a canvas whose text baseline is the top of the line.
+#[derive(Default)]
+struct Canvas {
+    elements: String,
+}
+
+impl Canvas {
+    fn add_text(&mut self, text: &str, font: Font, color: &str, x: f32, y: f32, anchor: Anchor) {
+        let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+
+        write!(
+            self.elements,
+            r#"<text x="{x}" y="{}" font-family="{}" font-size="{}" fill="{color}" stroke="{color}" stroke-width="{}" stroke-linejoin="round" text-anchor="{anchor}">{}</text>"#,
+            y + font.ascent(),
+            font.typeface.family(),
+            font.size,
+            font.stroke_width(),
+            escape(text),
+        )
+        .unwrap();
+    }
+
+    /// Adds `text`, shrinking the font until the text fits in `max_width`.
+    /// Every step down moves the text down a pixel, which keeps it close to
+    /// the middle of the line.
+    #[allow(clippy::too_many_arguments)]
+    fn fit_text(
+        &mut self,
+        text: &str,
+        mut font: Font,
+        color: &str,
+        x: f32,
+        mut y: f32,
+        max_width: f32,
+        anchor: Anchor,
+    ) {
+        y += 1.0;
+        while font.size > 1.0 && font.measure(text) > max_width {
+            font.size -= 1.0;
+            y += 1.0;
+        }
+
+        self.add_text(text, font, color, x, y, anchor);
+    }
+
+    /// Adds `text` left-aligned, broken into lines of at most `line_length`
+    /// characters where possible.
+    fn wrap_text(
+        &mut self,
+        text: &str,
+        font: Font,
+        color: &str,
+        x: f32,
+        y: f32,
+        line_length: usize,
+    ) {
+        for (index, line) in wrap(text, line_length).iter().enumerate() {
+            let line_y = y + index as f32 * font.size * LINE_HEIGHT;
+            self.add_text(line, font, color, x, line_y, Anchor::Start);
+        }
+    }
+}
+
+/// Breaks `text` at whitespace into lines of at most `line_length`
+/// characters. Words longer than that get a line of their own.
+fn wrap(text: &str, line_length: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > line_length {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats the issue date of a certificate, given in milliseconds since the
+/// Unix epoch, as `YYYY-MM-DD`.
+fn format_issue_date(date: &str) -> String {
+    date.parse()
+        .ok()
+        .and_then(|milliseconds| Utc.timestamp_millis_opt(milliseconds).single())
+        .map_or_else(|| date.to_string(), |date| date.format("%F").to_string())
+}
+
+/// Builds the SVG document of the certificate from its template, with the
+/// text still to be laid out.
+fn compose(certificate: &CertificateData) -> String {
+    let template = template::for_program(&certificate.program_code);
+    let layout = template.layout;
+    let mut canvas = Canvas::default();
+
+    canvas.add_text(
+        "CERTIFICATE OF ACHIEVEMENT",
+        TITLE_FONT,
+        BLUE,
+        X_CENTER,
+        layout.title,
+        Anchor::Middle,
+    );
+    canvas.fit_text(
+        &certificate.account_name,
+        ACCOUNT_NAME_FONT,
+        BLACK,
+        X_CENTER,
+        layout.account_name,
+        BODY_WIDTH,
+        Anchor::Middle,
+    );
+    canvas.wrap_text(
+        &certificate.program_description,
+        DESCRIPTION_FONT,
+        GRAY,
+        LEFT_PADDING,
+        layout.description,
+        layout.description_line_length,
+    );
+    canvas.add_text(
+        &certificate.program_name,
+        PROGRAM_FONT,
+        BLACK,
+        X_CENTER,
+        layout.program_name,
+        Anchor::Middle,
+    );
+    canvas.add_text(
+        "Instructor:",
+        FIELD_LABEL_FONT,
+        GRAY,
+        LEFT_PADDING,
+        layout.issued,
+        Anchor::Start,
+    );
+    canvas.fit_text(
+        &certificate.instructor,
+        INSTRUCTOR_FONT,
+        BLACK,
+        X_POSITION_OF_INSTRUCTOR,
+        layout.issued,
+        INSTRUCTOR_WIDTH,
+        Anchor::Start,
+    );
+    canvas.add_text(
+        "Issued:",
+        FIELD_LABEL_FONT,
+        GRAY,
+        X_POSITION_OF_DATE_LABEL,
+        layout.issued,
+        Anchor::End,
+    );
+    canvas.add_text(
+        &format_issue_date(&certificate.date),
+        DATE_FONT,
+        BLACK,
+        X_POSITION_OF_DATE,
+        layout.issued,
+        Anchor::End,
+    );
+
+    if let Ok(expiration) = DateTime::parse_from_rfc3339(&certificate.expiration) {
+        let expiration_date = expiration.format("%F").to_string();
+
+        if expiration < Utc::now() {
+            canvas.add_text(
+                "Expired:",
+                FIELD_LABEL_FONT,
+                GRAY,
+                X_POSITION_OF_DATE_LABEL,
+                layout.expiration,
+                Anchor::End,
+            );
+        } else {
+            canvas.add_text(
+                "Expiration*:",
+                FIELD_LABEL_FONT,
+                GRAY,
+                X_POSITION_OF_DATE_LABEL,
+                layout.expiration,
+                Anchor::End,
+            );
+            canvas.wrap_text(
+                &format!("* Will expire after the first 6-month period of inactivity of this mainnet account (which currently would be {expiration_date} if no future activity)"),
+                EXPIRATION_EXPLANATION_FONT,
+                GRAY,
+                LEFT_PADDING,
+                layout.expiration_explanation,
+                EXPIRATION_EXPLANATION_LINE_LENGTH,
+            );
+        }
+
+        canvas.add_text(
+            &expiration_date,
+            DATE_FONT,
+            BLACK,
+            X_POSITION_OF_DATE,
+            layout.expiration,
+            Anchor::End,
+        );
+    }
+
+    canvas.add_text(
+        &certificate.token_id,
+        TOKEN_ID_FONT,
+        BLACK,
+        X_CENTER,
+        layout.token_id,
+        Anchor::Middle,
+    );
+
+    // The text goes last so that it's drawn over the background
+    let end = template.background.rfind("</svg>").unwrap_or_default();
+    format!(
+        "{}{}{}",
+        &template.background[..end],
+        canvas.elements,
+        &template.background[end..]
+    )
+}
+
+/// Renders the image of a certificate. Text is converted to paths, so the
+/// SVG doesn't depend on the fonts installed where it's displayed.
+fn render(certificate: &CertificateData, format: Format) -> APIResult<Vec<u8>> {
+    let render_error = |error: &dyn std::fmt::Display| {
+        eprintln!(
+            "Rendering certificate {} failed: {error}",
+            certificate.token_id
+        );
+        errors::APIError::ImageRenderError {
+            token_id: certificate.token_id.clone(),
+        }
+    };
+
+    let options = usvg::Options {
+        fontdb: Arc::clone(&FONT_DATABASE),
+        ..usvg::Options::default()
+    };
+
+    let tree = usvg::Tree::from_str(&compose(certificate), &options)
+        .map_err(|error| render_error(&error))?;
+
+    match format {
+        Format::Svg => Ok(tree.to_string(&usvg::WriteOptions::default()).into_bytes()),
+        Format::Png => {
+            let mut pixmap = tiny_skia::Pixmap::new(WIDTH, HEIGHT).unwrap();
+            resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+            pixmap.encode_png().map_err(|error| render_error(&error))
+        }
+    }
+}
+
+/// Rendering takes long enough to stall the runtime, so it runs on the
+/// blocking thread pool.
+pub async fn response(certificate: CertificateData, format: Format) -> APIResult<Response> {
+    let token_id = certificate.token_id.clone();
+    let image = tokio::task::spawn_blocking(move || render(&certificate, format))
+        .await
+        .map_err(|error| {
+            eprintln!("Rendering certificate {token_id} failed: {error}");
+            errors::APIError::ImageRenderError { token_id }
+        })??;
+
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CACHE_CONTROL, format!("public, s-maxage={CACHE_SECONDS}")),
+        ],
+        image,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wrap, Format};
+
+    #[test]
+    fn wraps_text_at_whitespace() {
+        assert_eq!(
+            wrap("A certificate  of\nachievement", 14),
+            ["A certificate", "of achievement"]
+        );
+        assert_eq!(
+            wrap("supercalifragilistic word", 10),
+            ["supercalifragilistic", "word"]
+        );
+        assert!(wrap("", 10).is_empty());
+    }
+
+    #[test]
+    fn parses_image_file_names() {
+        assert!(matches!(
+            Format::from_file_name("abc.svg"),
+            Some(("abc", Format::Svg))
+        ));
+        assert!(matches!(
+            Format::from_file_name("abc.png"),
+            Some(("abc", Format::Png))
+        ));
+        assert!(Format::from_file_name("abc").is_none());
+        assert!(Format::from_file_name("abc.pdf").is_none());
+    }
+}
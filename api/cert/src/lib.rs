@@ -1,8 +1,24 @@
-use axum::{debug_handler, extract::Path, Json};
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    http::{header::ACCEPT_LANGUAGE, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::ChainClient;
 use errors::APIResult;
+use near_certification_tools::{CertificationEndorsement, LocalizedMetadata, Token};
 
+mod account;
 mod db;
+mod image;
+mod locale;
 mod query;
+mod template;
+
+pub use account::handler as account_handler;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct CertificateData {
@@ -12,19 +28,54 @@ pub struct CertificateData {
     program_name: String,
     program_code: String,
     program_description: String,
+    /// Language tag of the localized `program_name` and `program_description`,
+    /// `None` if they are in the default language.
+    locale: Option<String>,
+    /// Whether the certificate has not been invalidated.
+    valid: bool,
     instructor: String,
     account_name: String,
+    recipient_name: Option<String>,
+    grade: Option<String>,
+    score: Option<f64>,
+    max_score: Option<f64>,
+    level: Option<String>,
+    credits: Option<f64>,
+    endorsements: Vec<CertificationEndorsement>,
 }
 
 #[derive(serde::Deserialize)]
 struct CertificateExtraMetadata {
     authority_id: Option<String>,
     program: Option<String>,
+    original_recipient_name: Option<String>,
+    original_recipient_name_commitment: Option<String>,
     valid: bool,
+    grade: Option<String>,
+    score: Option<f64>,
+    max_score: Option<f64>,
+    level: Option<String>,
+    credits: Option<f64>,
+    localized: Option<BTreeMap<String, LocalizedMetadata>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CertificateQuery {
+    /// Name and salt of a recipient whose name is only committed to on-chain.
+    name: Option<String>,
+    salt: Option<String>,
+    /// Preferred language tag, overriding the `Accept-Language` header.
+    lang: Option<String>,
 }
 
-async fn get_cert_data(token_id: &str) -> APIResult<CertificateData> {
-    let token = common::get_token(token_id).await?;
+/// Reads the certificate stored in `token`, along with the commitment to the
+/// recipient name if any. The expiration and endorsements are left empty until
+/// `look_up` fills them in.
+fn read_token(
+    token: Token,
+    preferred_languages: &[String],
+) -> APIResult<(CertificateData, Option<String>)> {
+    let token_id = token.token_id.as_str();
 
     let Some(metadata) = token.metadata else {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
@@ -33,11 +84,11 @@ async fn get_cert_data(token_id: &str) -> APIResult<CertificateData> {
     let Some(issued_at) = metadata.issued_at else {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
     };
+    // Checked up front so that lists skip the token instead of failing on it
+    db::parse_issued_at(&issued_at)?;
 
     let account_name = token.owner_id.to_string();
 
-    let expiration = db::get_expiration(&account_name, &issued_at.clone()).await?;
-
     let Some(extra_metadata) = metadata.extra else {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
     };
@@ -46,12 +97,6 @@ async fn get_cert_data(token_id: &str) -> APIResult<CertificateData> {
         return Err(errors::APIError::ExtraMetadataInvalid { extra: extra_metadata.to_string() });
     };
 
-    if !extra_metadata.valid {
-        return Err(errors::APIError::CertificateInvalid {
-            token_id: token_id.to_string(),
-        });
-    }
-
     let Some(program_code) = extra_metadata.program else {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
     };
@@ -64,23 +109,114 @@ async fn get_cert_data(token_id: &str) -> APIResult<CertificateData> {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
     };
 
+    let localized = extra_metadata.localized.unwrap_or_default();
+    let locale = locale::best_match(
+        preferred_languages,
+        &localized.keys().map(String::as_str).collect::<Vec<_>>(),
+    );
+    let (program_name, program_description) = match locale.and_then(|tag| localized.get(tag)) {
+        Some(LocalizedMetadata { title, description }) => (
+            title.clone().unwrap_or(program_name),
+            description.clone().unwrap_or(program_description),
+        ),
+        None => (program_name, program_description),
+    };
+
     let Some(instructor) = extra_metadata.authority_id else {
         return Err(errors::APIError::NoMetadataError { token_id: token_id.to_string() });
     };
 
-    Ok(CertificateData {
-        token_id: token.token_id,
+    let certificate = CertificateData {
+        token_id: token_id.to_string(),
         date: issued_at,
-        expiration,
+        expiration: String::new(),
         account_name,
+        recipient_name: extra_metadata.original_recipient_name,
         instructor,
         program_name,
         program_code,
         program_description,
-    })
+        locale: locale.map(str::to_string),
+        valid: extra_metadata.valid,
+        grade: extra_metadata.grade,
+        score: extra_metadata.score,
+        max_score: extra_metadata.max_score,
+        level: extra_metadata.level,
+        credits: extra_metadata.credits,
+        endorsements: vec![],
+    };
+
+    Ok((certificate, extra_metadata.original_recipient_name_commitment))
 }
 
+/// Fills in the expiration and endorsements of a certificate from `read_token`.
+async fn look_up(
+    chain_client: &dyn ChainClient,
+    database: &db::Database,
+    certificate: &mut CertificateData,
+) -> APIResult<()> {
+    certificate.expiration =
+        db::get_expiration(database, &certificate.account_name, &certificate.date).await?;
+    certificate.endorsements =
+        common::get_endorsements(chain_client, &certificate.token_id).await?;
+
+    Ok(())
+}
+
+async fn get_cert_data(
+    chain_client: &dyn ChainClient,
+    token_id: &str,
+    CertificateQuery { name, salt, .. }: CertificateQuery,
+    preferred_languages: &[String],
+) -> APIResult<CertificateData> {
+    let token = common::get_token(chain_client, token_id).await?;
+    let (mut certificate, name_commitment) = read_token(token, preferred_languages)?;
+
+    if !certificate.valid {
+        return Err(errors::APIError::CertificateInvalid {
+            token_id: token_id.to_string(),
+        });
+    }
+
+    if let (Some(_), Some(name), Some(salt)) = (name_commitment, name, salt) {
+        if !common::verify_recipient_name(chain_client, token_id, &name, &salt).await? {
+            return Err(errors::APIError::RecipientNameMismatch {
+                token_id: token_id.to_string(),
+            });
+        }
+        certificate.recipient_name = Some(name);
+    }
+
+    look_up(chain_client, &db::Database::default(), &mut certificate).await?;
+
+    Ok(certificate)
+}
+
+/// Returns the certificate data, or its image for a `{token_id}.svg` or
+/// `{token_id}.png` path.
 #[debug_handler]
-pub async fn handler(Path(token_id): Path<String>) -> APIResult<Json<CertificateData>> {
-    Ok(Json(get_cert_data(&token_id).await?))
+pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
+    Path(token_id): Path<String>,
+    Query(query): Query<CertificateQuery>,
+    headers: HeaderMap,
+) -> APIResult<Response> {
+    let preferred_languages = locale::preferred_languages(
+        query.lang.as_deref(),
+        headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|accept_language| accept_language.to_str().ok()),
+    );
+
+    let (token_id, format) = match image::Format::from_file_name(&token_id) {
+        Some((token_id, format)) => (token_id, Some(format)),
+        None => (token_id.as_str(), None),
+    };
+    let certificate =
+        get_cert_data(chain_client.as_ref(), token_id, query, &preferred_languages).await?;
+
+    match format {
+        Some(format) => image::response(certificate, format).await,
+        None => Ok(Json(certificate).into_response()),
+    }
 }
@@ -0,0 +1,61 @@
+/// Language tags preferred by a request, most preferred first: the `?lang=`
+/// parameter if given, otherwise the tags of the `Accept-Language` header
+/// ordered by quality value.
+pub fn preferred_languages(lang: Option<&str>, accept_language: Option<&str>) -> Vec<String> {
+    if let Some(lang) = lang {
+        return vec![lang.trim().to_string()];
+    }
+
+    let Some(accept_language) = accept_language else {
+        return vec![];
+    };
+
+    let mut languages: Vec<(String, f32)> = accept_language
+        .split(',')
+        .filter_map(|language| {
+            let mut parts = language.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.trim().parse().ok())?;
+
+            (!tag.is_empty() && tag != "*" && quality > 0.0).then(|| (tag.to_string(), quality))
+        })
+        .collect();
+
+    // Stable, so equally preferred tags keep the header order
+    languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Returns the available language tag best matching the preferred ones.
+///
+/// A preferred tag matches an available tag equal to it or to one of its
+/// prefixes (e.g. `es-MX` matches `es`), or otherwise an available tag it
+/// is a prefix of (e.g. `uk` matches `uk-UA`).
+pub fn best_match<'a>(preferred: &[String], available: &[&'a str]) -> Option<&'a str> {
+    preferred.iter().find_map(|preferred| {
+        let preferred = preferred.to_ascii_lowercase();
+
+        let mut prefix = preferred.as_str();
+        loop {
+            if let Some(tag) = available
+                .iter()
+                .find(|tag| tag.eq_ignore_ascii_case(prefix))
+            {
+                return Some(*tag);
+            }
+
+            match prefix.rsplit_once('-') {
+                Some((shorter, _)) => prefix = shorter,
+                None => break,
+            }
+        }
+
+        available.iter().copied().find(|tag| {
+            tag.to_ascii_lowercase()
+                .starts_with(&format!("{preferred}-"))
+        })
+    })
+}
@@ -0,0 +1,100 @@
+/// Vertical positions of the certificate fields, in pixels from the top of the
+/// 1080x1080 image.
+pub struct Layout {
+    pub title: f32,
+    pub account_name: f32,
+    pub description: f32,
+    /// Maximum number of characters in a line of the description.
+    pub description_line_length: usize,
+    pub program_name: f32,
+    pub issued: f32,
+    pub expiration: f32,
+    pub expiration_explanation: f32,
+    pub token_id: f32,
+}
+
+/// Design of the certificates of a program: a background image and the layout
+/// of the fields drawn on top of it.
+pub struct Template {
+    /// SVG document of the same size as the image.
+    pub background: &'static str,
+    pub layout: &'static Layout,
+}
+
+const STANDARD_LAYOUT: Layout = Layout {
+    title: 170.0,
+    account_name: 304.0,
+    description: 450.0,
+    description_line_length: 60,
+    program_name: 680.0,
+    issued: 807.0,
+    expiration: 850.0,
+    expiration_explanation: 910.0,
+    token_id: 995.0,
+};
+
+/// Templates by program code.
+const TEMPLATES: &[(&str, Template)] = &[
+    (
+        "Analyst",
+        Template {
+            background: include_str!("../assets/backgrounds/Analyst_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Architect",
+        Template {
+            background: include_str!("../assets/backgrounds/Architect_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Designer",
+        Template {
+            background: include_str!("../assets/backgrounds/Designer_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Developer",
+        Template {
+            background: include_str!("../assets/backgrounds/Developer_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Entrepreneur",
+        Template {
+            background: include_str!("../assets/backgrounds/Entrepreneur_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Instructor",
+        Template {
+            background: include_str!("../assets/backgrounds/Instructor_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+    (
+        "Security",
+        Template {
+            background: include_str!("../assets/backgrounds/Security_certificate.svg"),
+            layout: &STANDARD_LAYOUT,
+        },
+    ),
+];
+
+/// Used for programs without a template of their own.
+static DEFAULT_TEMPLATE: Template = Template {
+    background: include_str!("../assets/backgrounds/default.svg"),
+    layout: &STANDARD_LAYOUT,
+};
+
+pub fn for_program(program_code: &str) -> &'static Template {
+    TEMPLATES
+        .iter()
+        .find(|(code, _)| *code == program_code)
+        .map_or(&DEFAULT_TEMPLATE, |(_, template)| template)
+}
@@ -0,0 +1,67 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+/// Certificates minted together from one upload, see `POST /batches`.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct MintBatch {
+    pub batch_id: String,
+    pub rows: Vec<MintBatchRow>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct MintBatchRow {
+    /// Fixed when the batch is created, so minting a row again can't issue a
+    /// second certificate.
+    pub token_id: String,
+    pub recipient_id: String,
+    /// Arguments of `nft_mint`.
+    pub mint_args: serde_json::Value,
+    #[serde(flatten)]
+    pub status: MintBatchRowStatus,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MintBatchRowStatus {
+    Pending,
+    Succeeded,
+    Failed { message: String },
+}
+
+/// IDs of the batches being minted, so that every batch is minted by a
+/// single task at a time. Kept in memory, as nothing is being minted after a
+/// restart.
+#[derive(Default)]
+pub struct MintingBatches(Mutex<HashSet<String>>);
+
+impl MintingBatches {
+    /// Marks `batch_id` as being minted until the returned guard is dropped,
+    /// or returns `None` if it already is.
+    pub fn start(self: &Arc<Self>, batch_id: &str) -> Option<MintingGuard> {
+        if !self.0.lock().unwrap().insert(batch_id.to_string()) {
+            return None;
+        }
+
+        Some(MintingGuard {
+            minting_batches: self.clone(),
+            batch_id: batch_id.to_string(),
+        })
+    }
+}
+
+pub struct MintingGuard {
+    minting_batches: Arc<MintingBatches>,
+    batch_id: String,
+}
+
+impl Drop for MintingGuard {
+    fn drop(&mut self) {
+        self.minting_batches
+            .0
+            .lock()
+            .unwrap()
+            .remove(&self.batch_id);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use errors::APIResult;
+use near_certification_tools::{
+    AccountId, CertificationContract, CertificationContractInitOptions, MintNFT,
+};
+use near_contract_standards::non_fungible_token::{
+    core::NonFungibleTokenCore,
+    enumeration::NonFungibleTokenEnumeration,
+    metadata::{NFTContractMetadata, NFT_METADATA_SPEC},
+    TokenId,
+};
+use near_primitives::{
+    errors::{ActionError, ActionErrorKind, TxExecutionError},
+    hash::CryptoHash,
+    transaction::Action,
+    views::FinalExecutionStatus,
+};
+use near_sdk::{
+    env, json_types::U128, mock, test_utils::VMContextBuilder, Balance, MockedBlockchain,
+    RuntimeFeesConfig, StorageUsage, VMConfig,
+};
+use near_vm_errors::FunctionCallErrorSer;
+use serde::de::DeserializeOwned;
+
+const CONTRACT_ID: &str = "certification.test.near";
+const CONTRACT_BALANCE: Balance = 100_000_000_000_000_000_000_000_000;
+const BLOCK_TIME: u64 = 1_000_000_000;
+
+#[derive(Clone, Default)]
+struct FakeState {
+    storage: HashMap<Vec<u8>, Vec<u8>>,
+    storage_usage: StorageUsage,
+    block_height: u64,
+}
+
+/// `ChainClient` running `CertificationContract` in a mocked blockchain held
+/// in memory. Transactions are applied atomically and a failed contract
+/// assertion is reported as a `FunctionCallError`, as it would be on-chain.
+/// `env::panic_str` can't unwind out of the mocked host and aborts the
+/// process instead, so the conditions under which the contract calls it are
+/// checked before the call. Broadcast transactions stay pending until
+/// `process_transactions` is called.
+pub struct FakeChainClient {
+    state: Mutex<FakeState>,
+    transactions: Mutex<Vec<FakeTransaction>>,
+}
+
+/// Transaction broadcast with `broadcast_transaction_to_certs`, executed by
+/// `process_transactions`.
+struct FakeTransaction {
+    transaction_hash: CryptoHash,
+    signer_id: AccountId,
+    actions: Vec<Action>,
+    status: Option<FinalExecutionStatus>,
+}
+
+impl FakeChainClient {
+    /// Deploys a fresh contract owned by `owner_id`, who is also an issuer.
+    pub fn new(owner_id: &str) -> Self {
+        let owner_id: AccountId = owner_id.parse().unwrap();
+        let mut state = FakeState::default();
+
+        run(&mut state, &owner_id, 0, false, || {
+            let mut contract = CertificationContract::new(
+                owner_id.clone(),
+                NFTContractMetadata {
+                    spec: NFT_METADATA_SPEC.to_string(),
+                    name: "Test Certifications".to_string(),
+                    symbol: "TEST".to_string(),
+                    icon: None,
+                    base_uri: None,
+                    reference: None,
+                    reference_hash: None,
+                },
+                CertificationContractInitOptions {
+                    can_transfer: false,
+                    can_invalidate: true,
+                    upgrade_authority_id: None,
+                },
+            );
+            contract.add_issuer(owner_id.clone(), None, None);
+            env::state_write(&contract);
+        })
+        .expect("Contract initialization failed");
+
+        FakeChainClient {
+            state: Mutex::new(state),
+            transactions: Mutex::default(),
+        }
+    }
+
+    /// Executes the transactions broadcast since the last call, as the next
+    /// block would.
+    pub fn process_transactions(&self) {
+        let mut transactions = self.transactions.lock().unwrap();
+
+        for transaction in transactions.iter_mut() {
+            if transaction.status.is_none() {
+                let actions = transaction.actions.clone();
+                transaction.status = Some(self.execute(&transaction.signer_id, actions));
+            }
+        }
+    }
+
+    /// Applies the actions of a transaction, reverting them all if one fails.
+    fn execute(&self, signer_id: &AccountId, actions: Vec<Action>) -> FinalExecutionStatus {
+        let mut guard = self.state.lock().unwrap();
+        let mut state = guard.clone();
+        let mut value = vec![];
+
+        for (index, action) in actions.into_iter().enumerate() {
+            let Action::FunctionCall(function_call) = action else {
+                panic!("FakeChainClient only supports function call actions");
+            };
+
+            match call(
+                &mut state,
+                signer_id,
+                &function_call.method_name,
+                &function_call.args,
+                function_call.deposit,
+            ) {
+                Ok(result) => value = result,
+                Err(message) => {
+                    return FinalExecutionStatus::Failure(TxExecutionError::ActionError(
+                        ActionError {
+                            index: Some(index as u64),
+                            kind: ActionErrorKind::FunctionCallError(
+                                FunctionCallErrorSer::ExecutionError(message),
+                            ),
+                        },
+                    ))
+                }
+            }
+        }
+
+        *guard = state;
+
+        FinalExecutionStatus::SuccessValue(value)
+    }
+
+    fn view(&self, method_name: &str, args: &[u8]) -> Result<Vec<u8>, String> {
+        let mut state = self.state.lock().unwrap();
+        let predecessor_id = CONTRACT_ID.parse().unwrap();
+
+        run(&mut state, &predecessor_id, 0, true, || {
+            let contract: CertificationContract = env::state_read().unwrap();
+
+            match method_name {
+                "nft_token" => {
+                    let TokenIdArgs { token_id } = parse_args(args);
+                    serde_json::to_vec(&contract.nft_token(token_id))
+                }
+                "nft_tokens_for_owner" => {
+                    let TokensForOwnerArgs {
+                        account_id,
+                        from_index,
+                        limit,
+                    } = parse_args(args);
+                    serde_json::to_vec(
+                        &contract.nft_tokens_for_owner(account_id, from_index, limit),
+                    )
+                }
+                "nft_supply_for_owner" => {
+                    let AccountIdArgs { account_id } = parse_args(args);
+                    serde_json::to_vec(&contract.nft_supply_for_owner(account_id))
+                }
+                "cert_endorsements" => {
+                    let TokenIdArgs { token_id } = parse_args(args);
+                    serde_json::to_vec(&contract.cert_endorsements(token_id))
+                }
+                "cert_verify_recipient_name" => {
+                    let VerifyRecipientNameArgs {
+                        token_id,
+                        name,
+                        salt,
+                    } = parse_args(args);
+                    serde_json::to_vec(&contract.cert_verify_recipient_name(token_id, name, salt))
+                }
+                _ => panic!("Method {method_name} not found"),
+            }
+            .unwrap()
+        })
+    }
+}
+
+#[async_trait]
+impl crate::ChainClient for FakeChainClient {
+    async fn view_certs_contract(
+        &self,
+        method_name: &str,
+        args: serde_json::Value,
+    ) -> APIResult<Vec<u8>> {
+        self.view(method_name, args.to_string().as_bytes())
+            .map_err(|_| errors::APIError::RpcError)
+    }
+
+    async fn send_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: crate::SignerData,
+    ) -> APIResult<FinalExecutionStatus> {
+        let signer_id = parse_signer_id(signer_data)?;
+
+        Ok(self.execute(&signer_id, actions))
+    }
+
+    async fn broadcast_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: crate::SignerData,
+    ) -> APIResult<CryptoHash> {
+        let signer_id = parse_signer_id(signer_data)?;
+
+        let mut transactions = self.transactions.lock().unwrap();
+        let transaction_hash = CryptoHash::hash_bytes(&transactions.len().to_le_bytes());
+        transactions.push(FakeTransaction {
+            transaction_hash,
+            signer_id,
+            actions,
+            status: None,
+        });
+
+        Ok(transaction_hash)
+    }
+
+    async fn transaction_status(
+        &self,
+        transaction_hash: &CryptoHash,
+        _signer_id: &near_primitives::types::AccountId,
+    ) -> APIResult<Option<FinalExecutionStatus>> {
+        Ok(self
+            .transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|transaction| transaction.transaction_hash == *transaction_hash)
+            .and_then(|transaction| transaction.status.clone()))
+    }
+}
+
+fn parse_signer_id(signer_data: crate::SignerData) -> APIResult<AccountId> {
+    match signer_data.account_id.parse() {
+        Ok(signer_id) => Ok(signer_id),
+        Err(_) => Err(errors::APIError::AccountInvalid {
+            account_id: signer_data.account_id,
+        }),
+    }
+}
+
+/// Applies a function call action of a transaction to `state`.
+fn call(
+    state: &mut FakeState,
+    predecessor_id: &AccountId,
+    method_name: &str,
+    args: &[u8],
+    deposit: Balance,
+) -> Result<Vec<u8>, String> {
+    run(state, predecessor_id, deposit, false, || {
+        let mut contract: CertificationContract = env::state_read().unwrap();
+
+        let result = match method_name {
+            "nft_mint" => {
+                let MintNFT {
+                    token_id,
+                    receiver_account_id,
+                    token_metadata,
+                    certification_metadata,
+                    memo,
+                } = parse_args(args);
+                // Checked ahead of `internal_mint`, which uses `env::panic_str`
+                assert!(
+                    contract.nft_token(token_id.clone()).is_none(),
+                    "token_id must be unique"
+                );
+                serde_json::to_vec(&contract.nft_mint(
+                    token_id,
+                    receiver_account_id,
+                    token_metadata,
+                    certification_metadata,
+                    memo,
+                ))
+            }
+            "cert_invalidate" => {
+                let InvalidateArgs { token_id, memo } = parse_args(args);
+                serde_json::to_vec(&contract.cert_invalidate(token_id, memo))
+            }
+            "cert_invalidate_for_account" => {
+                let InvalidateForAccountArgs {
+                    account_id,
+                    memo,
+                    from_index,
+                    limit,
+                } = parse_args(args);
+                serde_json::to_vec(
+                    &contract.cert_invalidate_for_account(account_id, memo, from_index, limit),
+                )
+            }
+            _ => panic!("Method {method_name} not found"),
+        };
+
+        env::state_write(&contract);
+        result.unwrap()
+    })
+}
+
+/// Runs `f` in a mocked blockchain backed by `state`, keeping the storage
+/// changes unless `is_view`. Panics which unwind, such as those of `require!`
+/// in debug builds, are caught and returned as their message.
+fn run<Output>(
+    state: &mut FakeState,
+    predecessor_id: &AccountId,
+    deposit: Balance,
+    is_view: bool,
+    f: impl FnOnce() -> Output,
+) -> Result<Output, String> {
+    state.block_height += 1;
+
+    env::set_blockchain_interface(MockedBlockchain::new(
+        VMContextBuilder::new()
+            .current_account_id(CONTRACT_ID.parse().unwrap())
+            .signer_account_id(predecessor_id.clone())
+            .predecessor_account_id(predecessor_id.clone())
+            .account_balance(CONTRACT_BALANCE)
+            .attached_deposit(deposit)
+            .storage_usage(state.storage_usage)
+            .block_index(state.block_height)
+            .block_timestamp(state.block_height * BLOCK_TIME)
+            .is_view(is_view)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        vec![],
+        state.storage.clone(),
+        Default::default(),
+        None,
+    ));
+
+    let output = panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "Smart contract panicked".to_string())
+    })?;
+
+    if !is_view {
+        state.storage_usage = env::storage_usage();
+        state.storage = mock::with_mocked_blockchain(|b| b.take_storage());
+    }
+
+    Ok(output)
+}
+
+fn parse_args<Args: DeserializeOwned>(args: &[u8]) -> Args {
+    serde_json::from_slice(args).expect("Failed to deserialize input from JSON.")
+}
+
+#[derive(serde::Deserialize)]
+struct TokenIdArgs {
+    token_id: TokenId,
+}
+
+#[derive(serde::Deserialize)]
+struct AccountIdArgs {
+    account_id: AccountId,
+}
+
+#[derive(serde::Deserialize)]
+struct TokensForOwnerArgs {
+    account_id: AccountId,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+}
+
+#[derive(serde::Deserialize)]
+struct VerifyRecipientNameArgs {
+    token_id: TokenId,
+    name: String,
+    salt: String,
+}
+
+#[derive(serde::Deserialize)]
+struct InvalidateArgs {
+    token_id: TokenId,
+    memo: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct InvalidateForAccountArgs {
+    account_id: AccountId,
+    memo: Option<String>,
+    from_index: Option<U128>,
+    limit: u64,
+}
@@ -0,0 +1,54 @@
+use near_primitives::hash::CryptoHash;
+
+/// Request sent with an `Idempotency-Key` header, whose response is replayed
+/// when the same key is sent again.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct IdempotencyRecord {
+    pub request_hash: CryptoHash,
+    /// `None` while the request is being handled.
+    pub response: Option<StoredResponse>,
+    /// The handler stopped without a response, e.g. cancelled by the timeout,
+    /// so the request may be sent again.
+    pub interrupted: bool,
+}
+
+/// Request extension of a request sent with an `Idempotency-Key` header.
+#[derive(Clone)]
+pub struct IdempotentRequest {
+    pub key: String,
+    pub request_hash: CryptoHash,
+    /// An earlier run of the request was interrupted, so it may already have
+    /// taken effect.
+    pub resumed: bool,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+impl IdempotencyRecord {
+    /// Hash identifying a request by its method, path, query and body.
+    pub fn request_hash(method: &str, path_and_query: &str, body: &[u8]) -> CryptoHash {
+        let mut request = format!("{method} {path_and_query}\n").into_bytes();
+        request.extend_from_slice(body);
+
+        CryptoHash::hash_bytes(&request)
+    }
+}
+
+impl IdempotentRequest {
+    /// Token ID of a certificate minted by this request, the same whenever
+    /// the request is retried so that it can't mint a second certificate.
+    pub fn token_id(&self) -> String {
+        let mut seed = self.key.as_bytes().to_vec();
+        seed.extend_from_slice(self.request_hash.as_ref());
+
+        CryptoHash::hash_bytes(&seed).as_ref()[..16]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
@@ -0,0 +1,30 @@
+use near_certification_tools::Token;
+use near_primitives::{hash::CryptoHash, types::AccountId};
+
+/// Mint transaction broadcast without waiting for its outcome, see
+/// `POST /mint-cert?async=true`.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct MintJob {
+    pub job_id: String,
+    pub transaction_hash: CryptoHash,
+    pub signer_id: AccountId,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: u64,
+    #[serde(flatten)]
+    pub status: MintJobStatus,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MintJobStatus {
+    Pending,
+    Succeeded {
+        token: Box<Token>,
+    },
+    Failed {
+        message: String,
+    },
+    /// The transaction stayed unknown to the RPC node past the deadline, so
+    /// it was most likely dropped and nothing was minted.
+    Expired,
+}
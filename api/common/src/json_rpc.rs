@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use errors::APIResult;
+use near_crypto::InMemorySigner;
+use near_jsonrpc_client::{errors::JsonRpcError, methods, JsonRpcClient};
+use near_jsonrpc_primitives::types::{query::QueryResponseKind, transactions::RpcTransactionError};
+use near_primitives::{
+    errors::InvalidTxError,
+    hash::CryptoHash,
+    transaction::{Action, SignedTransaction, Transaction},
+    types::{AccountId, BlockReference, FunctionArgs},
+    views::{AccessKeyView, CallResult, FinalExecutionStatus},
+};
+
+use crate::{ChainClient, NonceManager, SignerData};
+
+/// `ChainClient` talking to a NEAR node over JSON-RPC.
+#[derive(Clone)]
+pub struct JsonRpcChainClient {
+    client: JsonRpcClient,
+    contract_id: AccountId,
+    nonces: Arc<NonceManager>,
+}
+
+impl JsonRpcChainClient {
+    pub fn new(rpc_url: &str, contract_id: AccountId) -> Self {
+        JsonRpcChainClient {
+            client: JsonRpcClient::connect(rpc_url),
+            contract_id,
+            nonces: Arc::default(),
+        }
+    }
+
+    /// Connects to `RPC_URL` and targets `CERTIFICATE_CONTRACT_ACCOUNT_ID`.
+    pub fn from_env() -> Self {
+        let rpc_url = dotenvy::var("RPC_URL").expect("Missing RPC URL!");
+        let contract_id = dotenvy::var("CERTIFICATE_CONTRACT_ACCOUNT_ID")
+            .expect("Certificate contract account ID missing!")
+            .parse()
+            .expect("Invalid certificate contract account ID!");
+
+        Self::new(&rpc_url, contract_id)
+    }
+
+    pub async fn view_access_key(
+        &self,
+        InMemorySigner {
+            account_id,
+            public_key,
+            ..
+        }: &InMemorySigner,
+    ) -> APIResult<(CryptoHash, AccessKeyView)> {
+        let (kind, block_hash) = match self
+            .client
+            .call(methods::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: near_primitives::views::QueryRequest::ViewAccessKey {
+                    account_id: account_id.clone(),
+                    public_key: public_key.clone(),
+                },
+            })
+            .await
+        {
+            Ok(response) => (response.kind, response.block_hash),
+            Err(error) => {
+                log_rpc_error("query", &error);
+                return Err(errors::APIError::RpcError);
+            }
+        };
+
+        let access_key_view = match kind {
+            QueryResponseKind::AccessKey(access_key_view) => access_key_view,
+            _ => {
+                eprintln!("Received wrong query response kind.");
+                return Err(errors::APIError::RpcError);
+            }
+        };
+
+        Ok((block_hash, access_key_view))
+    }
+
+    pub async fn check_account_id(&self, account_id: &str) -> APIResult<bool> {
+        let Ok(account_id_parsed) = account_id.parse() else {
+            return Err(errors::APIError::AccountInvalid {
+                account_id: account_id.to_string(),
+            });
+        };
+
+        match self
+            .client
+            .call(methods::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: near_primitives::views::QueryRequest::ViewAccount {
+                    account_id: account_id_parsed,
+                },
+            })
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(_) => Err(errors::APIError::AccountNotFound {
+                account_id: account_id.parse().unwrap(),
+            }),
+        }
+    }
+
+    /// Signs `actions` with the next nonce of the access key of `signer`. The
+    /// access key is only locked while the nonce is taken, so transactions of
+    /// the same key are sent concurrently.
+    async fn sign_transaction(
+        &self,
+        signer: &InMemorySigner,
+        actions: Vec<Action>,
+    ) -> APIResult<SignedTransaction> {
+        let mut access_key = self.nonces.lock(&signer.public_key).await;
+        let (nonce, block_hash) = match access_key.next_nonce() {
+            Some(next_nonce) => next_nonce,
+            None => {
+                let (block_hash, access_key_view) = self.view_access_key(signer).await?;
+                access_key.sync(access_key_view.nonce, block_hash);
+                access_key.next_nonce().unwrap()
+            }
+        };
+        drop(access_key);
+
+        let transaction = Transaction {
+            signer_id: signer.account_id.clone(),
+            public_key: signer.public_key.clone(),
+            nonce,
+            receiver_id: self.contract_id.clone(),
+            block_hash,
+            actions,
+        };
+
+        Ok(transaction.sign(signer))
+    }
+
+    /// Forgets the cached nonce of the access key of `signer` after a failed
+    /// broadcast, which may or may not have used it up.
+    async fn reset_nonce(&self, signer: &InMemorySigner) {
+        self.nonces.lock(&signer.public_key).await.reset();
+    }
+}
+
+#[async_trait]
+impl ChainClient for JsonRpcChainClient {
+    async fn view_certs_contract(
+        &self,
+        method_name: &str,
+        args: serde_json::Value,
+    ) -> APIResult<Vec<u8>> {
+        let args = FunctionArgs::from(args.to_string().into_bytes());
+
+        let Ok(near_jsonrpc_primitives::types::query::RpcQueryResponse { kind, .. }) = self
+            .client
+            .call(methods::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: near_primitives::views::QueryRequest::CallFunction {
+                    account_id: self.contract_id.clone(),
+                    method_name: method_name.to_string(),
+                    args,
+                },
+            })
+            .await
+        else {
+            return Err(errors::APIError::RpcError);
+        };
+
+        let QueryResponseKind::CallResult(CallResult { result, .. }) = kind else {
+            return Err(errors::APIError::RpcError);
+        };
+
+        Ok(result)
+    }
+
+    async fn send_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: SignerData,
+    ) -> APIResult<FinalExecutionStatus> {
+        let signers = signer_data.signers()?;
+        let signer = self.nonces.choose_signer(&signers);
+
+        // A nonce error means the cached nonce is stale, e.g. because the key
+        // was used outside of this server or a transaction with a higher nonce
+        // overtook this one, so resync and try once more
+        let mut retried = false;
+        loop {
+            let request = methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
+                signed_transaction: self.sign_transaction(signer, actions.clone()).await?,
+            };
+
+            match self.client.call(request).await {
+                Ok(res) => return Ok(res.status),
+                Err(error) => {
+                    self.reset_nonce(signer).await;
+
+                    if !retried && is_invalid_nonce(&error) {
+                        retried = true;
+                        continue;
+                    }
+
+                    log_rpc_error("broadcast_tx_commit", &error);
+                    return Err(errors::APIError::RpcError);
+                }
+            }
+        }
+    }
+
+    async fn broadcast_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: SignerData,
+    ) -> APIResult<CryptoHash> {
+        let signers = signer_data.signers()?;
+        let signer = self.nonces.choose_signer(&signers);
+
+        let request = methods::broadcast_tx_async::RpcBroadcastTxAsyncRequest {
+            signed_transaction: self.sign_transaction(signer, actions).await?,
+        };
+
+        match self.client.call(request).await {
+            Ok(transaction_hash) => Ok(transaction_hash),
+            Err(error) => {
+                self.reset_nonce(signer).await;
+
+                log_rpc_error("broadcast_tx_async", &error);
+                Err(errors::APIError::RpcError)
+            }
+        }
+    }
+
+    async fn transaction_status(
+        &self,
+        transaction_hash: &CryptoHash,
+        signer_id: &AccountId,
+    ) -> APIResult<Option<FinalExecutionStatus>> {
+        let request = methods::tx::RpcTransactionStatusRequest {
+            transaction_info: methods::tx::TransactionInfo::TransactionId {
+                hash: *transaction_hash,
+                account_id: signer_id.clone(),
+            },
+        };
+
+        match self.client.call(request).await {
+            Ok(outcome) => Ok(match outcome.status {
+                FinalExecutionStatus::NotStarted | FinalExecutionStatus::Started => None,
+                status => Some(status),
+            }),
+            Err(error) => match error.handler_error() {
+                Some(
+                    RpcTransactionError::UnknownTransaction { .. }
+                    | RpcTransactionError::TimeoutError,
+                ) => Ok(None),
+                _ => {
+                    log_rpc_error("tx", &error);
+                    Err(errors::APIError::RpcError)
+                }
+            },
+        }
+    }
+}
+
+fn is_invalid_nonce(error: &JsonRpcError<RpcTransactionError>) -> bool {
+    matches!(
+        error.handler_error(),
+        Some(RpcTransactionError::InvalidTransaction {
+            context: InvalidTxError::InvalidNonce { .. },
+        })
+    )
+}
+
+/// Logs the summary of a failed RPC call rather than the debug output of the
+/// error, which can hold whole server responses.
+fn log_rpc_error(method_name: &str, error: &dyn std::fmt::Display) {
+    eprintln!("RPC call {method_name} failed: {error}");
+}
@@ -1,28 +1,119 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
 use axum::extract::FromRef;
 use errors::APIResult;
-use near_certification_tools::Token;
-use near_jsonrpc_client::{methods, JsonRpcClient};
-use near_jsonrpc_primitives::types::query::QueryResponseKind;
+use near_certification_tools::{CertificationEndorsement, Token};
 use near_primitives::{
-    hash::CryptoHash,
-    transaction::{Action, Transaction},
-    types::{BlockReference, FunctionArgs},
-    views::{AccessKeyView, CallResult, FinalExecutionOutcomeView},
+    hash::CryptoHash, transaction::Action, types::AccountId, views::FinalExecutionStatus,
 };
 
+mod batch;
+#[cfg(feature = "fake")]
+mod fake;
+mod idempotency;
+mod job;
+mod json_rpc;
+mod secret;
+mod signer;
+mod store;
+
+pub use batch::{MintBatch, MintBatchRow, MintBatchRowStatus, MintingBatches, MintingGuard};
+#[cfg(feature = "fake")]
+pub use fake::FakeChainClient;
+pub use idempotency::{IdempotencyRecord, IdempotentRequest, StoredResponse};
+pub use job::{MintJob, MintJobStatus};
+pub use json_rpc::JsonRpcChainClient;
+pub use secret::Secret;
+pub use signer::{AccessKeyLock, NonceManager};
+pub use store::{now_ms, Store};
+
 pub const TGAS: u64 = 1_000_000_000_000;
 pub const NEAR: u128 = 1_000_000_000_000_000_000_000_000;
 pub const YOCTO_NEAR: u128 = 1;
+/// Number of tokens requested per `nft_tokens_for_owner` call.
+pub const TOKENS_PAGE_SIZE: u64 = 50;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct SignerData {
     pub account_id: String,
-    pub private_key: String,
+    pub private_key: Secret,
+    /// Additional access keys of `account_id`, taken in turn with
+    /// `private_key` so that transactions can be sent in parallel.
+    pub key_pool: Vec<Secret>,
+}
+
+impl SignerData {
+    /// Signers for `private_key` followed by every key of `key_pool`.
+    pub fn signers(&self) -> APIResult<Vec<near_crypto::InMemorySigner>> {
+        let Ok(account_id) = self.account_id.parse::<near_primitives::types::AccountId>() else {
+            return Err(errors::APIError::AccountInvalid {
+                account_id: self.account_id.clone(),
+            });
+        };
+
+        std::iter::once(&self.private_key)
+            .chain(&self.key_pool)
+            .enumerate()
+            .map(|(key_index, private_key)| {
+                let Ok(secret_key) = private_key.expose().parse() else {
+                    return Err(errors::APIError::PrivateKeyInvalid { key_index });
+                };
+
+                Ok(near_crypto::InMemorySigner::from_secret_key(
+                    account_id.clone(),
+                    secret_key,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Access to the certification contract, so handlers can run against a live
+/// node or, in tests, against a local copy of the contract.
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Calls a view method of the certification contract and returns the raw
+    /// JSON result.
+    async fn view_certs_contract(
+        &self,
+        method_name: &str,
+        args: serde_json::Value,
+    ) -> APIResult<Vec<u8>>;
+
+    /// Signs `actions` as `signer_data` and sends them to the certification
+    /// contract, waiting for the final execution status.
+    async fn send_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: SignerData,
+    ) -> APIResult<FinalExecutionStatus>;
+
+    /// Like `send_transaction_to_certs`, but returns the hash of the
+    /// transaction as soon as it is broadcast.
+    async fn broadcast_transaction_to_certs(
+        &self,
+        actions: Vec<Action>,
+        signer_data: SignerData,
+    ) -> APIResult<CryptoHash>;
+
+    /// Final execution status of a transaction signed by `signer_id`, or
+    /// `None` while it is still pending.
+    async fn transaction_status(
+        &self,
+        transaction_hash: &CryptoHash,
+        signer_id: &AccountId,
+    ) -> APIResult<Option<FinalExecutionStatus>>;
 }
 
 #[derive(Clone, FromRef)]
 pub struct AppData {
     pub signer_data: SignerData,
+    pub chain_client: Arc<dyn ChainClient>,
+    pub mint_jobs: Arc<Store<MintJob>>,
+    pub mint_batches: Arc<Store<MintBatch>>,
+    pub minting_batches: Arc<MintingBatches>,
+    pub idempotency_keys: Arc<Store<IdempotencyRecord>>,
 }
 
 impl AppData {
@@ -31,194 +122,156 @@ impl AppData {
             .expect("No authority account ID in environment!");
         let private_key = dotenvy::var("ISSUING_AUTHORITY_PRIVATE_KEY")
             .expect("No authority private key in environment!");
+        let key_pool = dotenvy::var("ISSUING_AUTHORITY_KEY_POOL")
+            .map(|key_pool| {
+                key_pool
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|private_key| !private_key.is_empty())
+                    .map(Secret::new)
+                    .collect()
+            })
+            .unwrap_or_default();
 
         AppData {
             signer_data: SignerData {
                 account_id,
-                private_key,
+                private_key: Secret::new(private_key),
+                key_pool,
             },
+            chain_client: Arc::new(JsonRpcChainClient::from_env()),
+            mint_jobs: Arc::new(open_store("mint_jobs.json", MINT_JOB_TTL)),
+            mint_batches: Arc::new(open_store("mint_batches.json", MINT_BATCH_TTL)),
+            minting_batches: Arc::default(),
+            idempotency_keys: Arc::new(open_store("idempotency_keys.json", IDEMPOTENCY_KEY_TTL)),
         }
     }
 }
 
-fn connect_rpc() -> JsonRpcClient {
-    let rpc_url = dotenvy::var("RPC_URL").expect("Missing RPC URL!");
-
-    JsonRpcClient::connect(&rpc_url)
-}
-
-pub async fn view_access_key(
-    account_id: &str,
-    private_key: &str,
-) -> APIResult<(CryptoHash, AccessKeyView)> {
-    let Ok(account_id) = account_id.parse() else {
-        return Err(errors::APIError::AccountInvalid { account_id: account_id.to_string() });
-    };
-
-    let Ok(private_key) = private_key.parse() else {
-        return Err(errors::APIError::PrivateKeyInvalid { private_key: private_key.to_string() });
-    };
-
-    let near_crypto::InMemorySigner {
-        account_id,
-        public_key,
-        ..
-    } = near_crypto::InMemorySigner::from_secret_key(account_id, private_key);
-    println!("{account_id:?}");
-
-    let (kind, block_hash) = match connect_rpc()
-        .call(methods::query::RpcQueryRequest {
-            block_reference: BlockReference::latest(),
-            request: near_primitives::views::QueryRequest::ViewAccessKey {
-                account_id,
-                public_key,
-            },
-        })
-        .await
-    {
-        Ok(response) => (response.kind, response.block_hash),
-        Err(error) => {
-            eprintln!("{error:?}");
-            return Err(errors::APIError::ServerError);
-        }
-    };
+/// How long records are kept after their last update.
+const MINT_JOB_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const MINT_BATCH_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
-    let access_key_view = match kind {
-        QueryResponseKind::AccessKey(access_key_view) => access_key_view,
-        _ => {
-            eprintln!("Received wrong query response kind.");
-            return Err(errors::APIError::ServerError);
+/// Opens the store `file_name` in `DATA_DIR`, or an in-memory store if
+/// `DATA_DIR` isn't set.
+fn open_store<Record>(file_name: &str, ttl: Duration) -> Store<Record>
+where
+    Record: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    let store = match dotenvy::var("DATA_DIR") {
+        Ok(data_dir) if !data_dir.is_empty() => {
+            Store::open(std::path::Path::new(&data_dir).join(file_name))
+                .unwrap_or_else(|error| panic!("Couldn't open store {file_name}: {error}"))
         }
+        _ => Store::in_memory(),
     };
 
-    Ok((block_hash, access_key_view))
+    store.with_ttl(ttl)
 }
 
-pub async fn check_account_id(account_id: &str) -> APIResult<bool> {
-    let Ok(account_id_parsed) = account_id.parse() else {
-        return Err(errors::APIError::AccountInvalid { account_id: account_id.to_string() });
-    };
-
-    match connect_rpc()
-        .call(methods::query::RpcQueryRequest {
-            block_reference: BlockReference::latest(),
-            request: near_primitives::views::QueryRequest::ViewAccount {
-                account_id: account_id_parsed,
-            },
-        })
-        .await
-    {
-        Ok(_) => Ok(true),
-        Err(_) => Err(errors::APIError::AccountNotFound {
-            account_id: account_id.parse().unwrap(),
-        }),
+impl Default for AppData {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-pub async fn get_tokens_for_owner(account_id: &str) -> APIResult<Vec<Token>> {
-    let contract_id = dotenvy::var("CERTIFICATE_CONTRACT_ACCOUNT_ID")
-        .expect("Certificate contract account ID missing!")
-        .parse()
-        .unwrap();
-    let method_name = "nft_tokens_for_owner".to_string();
-    let args = FunctionArgs::from(
-        serde_json::json!({ "account_id": account_id })
-            .to_string()
-            .into_bytes(),
-    );
-
-    let Ok(near_jsonrpc_primitives::types::query::RpcQueryResponse {
-        kind, ..
-    }) = connect_rpc().call(methods::query::RpcQueryRequest {
-        block_reference: BlockReference::latest(),
-        request: near_primitives::views::QueryRequest::CallFunction {
-            account_id: contract_id,
-            method_name,
-            args,
-        },
-    }).await else {
-        return Err(errors::APIError::ServerError);
-    };
+async fn view_certs_contract<Type>(
+    chain_client: &dyn ChainClient,
+    method_name: &str,
+    args: serde_json::Value,
+) -> APIResult<Type>
+where
+    Type: serde::de::DeserializeOwned,
+{
+    deserialize_bytes(&chain_client.view_certs_contract(method_name, args).await?)
+}
 
-    let QueryResponseKind::CallResult(CallResult{result, ..}) = kind else {
-        return Err(errors::APIError::ServerError);
+/// Every token of `account_id`, fetched `TOKENS_PAGE_SIZE` at a time.
+pub async fn get_tokens_for_owner(
+    chain_client: &dyn ChainClient,
+    account_id: &str,
+) -> APIResult<Vec<Token>> {
+    let supply: String = view_certs_contract(
+        chain_client,
+        "nft_supply_for_owner",
+        serde_json::json!({ "account_id": account_id }),
+    )
+    .await?;
+    let Ok(supply) = supply.parse::<usize>() else {
+        return Err(errors::APIError::DeserializationError { string: supply });
     };
 
-    Ok(deserialize_bytes(&result)?)
-}
+    // The contract rejects a `from_index` past the last token, so stop at the
+    // supply rather than at an empty page
+    let mut tokens = vec![];
+    while tokens.len() < supply {
+        let page: Vec<Token> = view_certs_contract(
+            chain_client,
+            "nft_tokens_for_owner",
+            serde_json::json!({
+                "account_id": account_id,
+                "from_index": tokens.len().to_string(),
+                "limit": TOKENS_PAGE_SIZE,
+            }),
+        )
+        .await?;
 
-pub async fn get_token(token_id: &str) -> APIResult<Token> {
-    let account_id = dotenvy::var("CERTIFICATE_CONTRACT_ACCOUNT_ID")
-        .expect("Certificate contract account ID missing!")
-        .parse()
-        .unwrap();
-    let method_name = "nft_token".to_string();
-    let args = FunctionArgs::from(
-        serde_json::json!({ "token_id": token_id })
-            .to_string()
-            .into_bytes(),
-    );
-
-    let Ok(near_jsonrpc_primitives::types::query::RpcQueryResponse {
-        kind, ..
-    }) = connect_rpc().call(methods::query::RpcQueryRequest {
-        block_reference: BlockReference::latest(),
-        request: near_primitives::views::QueryRequest::CallFunction {
-            account_id,
-            method_name,
-            args,
-        },
-    }).await else {
-        return Err(errors::APIError::ServerError);
-    };
+        if page.is_empty() {
+            break;
+        }
+        tokens.extend(page);
+    }
 
-    let QueryResponseKind::CallResult(CallResult{result, ..}) = kind else {
-        return Err(errors::APIError::ServerError);
-    };
+    Ok(tokens)
+}
 
-    Ok(deserialize_bytes(&result)?)
+pub async fn get_token(chain_client: &dyn ChainClient, token_id: &str) -> APIResult<Token> {
+    match find_token(chain_client, token_id).await? {
+        Some(token) => Ok(token),
+        None => Err(errors::APIError::TokenNotFound {
+            token_id: token_id.to_string(),
+        }),
+    }
 }
 
-pub async fn send_transaction_to_certs(
-    actions: Vec<Action>,
-    SignerData {
-        account_id,
-        private_key,
-    }: SignerData,
-) -> APIResult<FinalExecutionOutcomeView> {
-    let (block_hash, access_key_view) = view_access_key(&account_id, &private_key).await?;
-
-    let nonce = access_key_view.nonce + 1;
-
-    let receiver_id = dotenvy::var("CERTIFICATE_CONTRACT_ACCOUNT_ID")
-        .expect("Certificate contract account ID missing!")
-        .parse()
-        .unwrap();
-    let signer = near_crypto::InMemorySigner::from_secret_key(
-        account_id.parse().unwrap(),
-        private_key.parse().unwrap(),
-    );
-
-    let transaction = Transaction {
-        signer_id: signer.account_id.clone(),
-        public_key: signer.public_key.clone(),
-        nonce,
-        receiver_id,
-        block_hash,
-        actions,
-    };
+/// Like `get_token`, but `None` if the token doesn't exist.
+pub async fn find_token(
+    chain_client: &dyn ChainClient,
+    token_id: &str,
+) -> APIResult<Option<Token>> {
+    view_certs_contract(
+        chain_client,
+        "nft_token",
+        serde_json::json!({ "token_id": token_id }),
+    )
+    .await
+}
 
-    let request = methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
-        signed_transaction: transaction.sign(&signer),
-    };
+pub async fn get_endorsements(
+    chain_client: &dyn ChainClient,
+    token_id: &str,
+) -> APIResult<Vec<CertificationEndorsement>> {
+    view_certs_contract(
+        chain_client,
+        "cert_endorsements",
+        serde_json::json!({ "token_id": token_id }),
+    )
+    .await
+}
 
-    match connect_rpc().call(request).await {
-        Ok(res) => Ok(res),
-        Err(error) => {
-            println!("{error:?}");
-            Err(errors::APIError::ServerError)
-        }
-    }
+pub async fn verify_recipient_name(
+    chain_client: &dyn ChainClient,
+    token_id: &str,
+    name: &str,
+    salt: &str,
+) -> APIResult<bool> {
+    view_certs_contract(
+        chain_client,
+        "cert_verify_recipient_name",
+        serde_json::json!({ "token_id": token_id, "name": name, "salt": salt }),
+    )
+    .await
 }
 
 pub fn deserialize_bytes<'input_lifetime, Type>(bytes: &'input_lifetime [u8]) -> APIResult<Type>
@@ -0,0 +1,87 @@
+use std::fmt;
+
+/// Secret such as a private key, redacted wherever it is formatted or
+/// serialized so that it can't end up in logs or responses. `expose` gives
+/// access to the value itself.
+#[derive(Clone, Default)]
+pub struct Secret(String);
+
+const REDACTED: &str = "[REDACTED]";
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(REDACTED)
+    }
+}
+
+impl serde::Serialize for Secret {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+    use near_crypto::{KeyType, SecretKey};
+
+    use super::Secret;
+    use crate::SignerData;
+
+    #[test]
+    fn redacts_secrets() {
+        let private_key = SecretKey::from_seed(KeyType::ED25519, "seed").to_string();
+        let secret = Secret::new(private_key.clone());
+
+        assert_eq!(secret.expose(), private_key);
+        for formatted in [
+            format!("{secret}"),
+            format!("{secret:?}"),
+            serde_json::to_string(&secret).unwrap(),
+        ] {
+            assert!(!formatted.contains("ed25519:"), "{formatted}");
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_keys_stay_out_of_errors() {
+        let private_key = SecretKey::from_seed(KeyType::ED25519, "seed").to_string();
+        let signer_data = SignerData {
+            account_id: "issuer.near".to_string(),
+            private_key: Secret::new(private_key),
+            key_pool: vec![Secret::new("ed25519:not-a-key")],
+        };
+
+        assert!(format!("{signer_data:?}").contains("issuer.near"));
+        assert!(!format!("{signer_data:?}").contains("ed25519:"));
+
+        let Err(error) = signer_data.signers() else {
+            panic!("The invalid key was accepted");
+        };
+        let message = error.to_string();
+        let debug = format!("{error:?}");
+        let body = hyper::body::to_bytes(error.into_response().into_body())
+            .await
+            .unwrap();
+
+        for formatted in [message, debug, String::from_utf8_lossy(&body).into()] {
+            assert!(!formatted.contains("ed25519:"), "{formatted}");
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use near_crypto::{InMemorySigner, PublicKey};
+use near_primitives::{hash::CryptoHash, types::Nonce};
+use tokio::sync::OwnedMutexGuard;
+
+/// Cached block hashes older than this are refreshed before signing, well
+/// within the validity period of a transaction.
+const MAX_BLOCK_HASH_AGE: Duration = Duration::from_secs(10 * 60);
+
+struct AccessKeyState {
+    nonce: Nonce,
+    block_hash: CryptoHash,
+    synced_at: Instant,
+}
+
+/// Hands out nonces for the access keys of the issuing account, so that
+/// concurrent requests don't sign transactions with the same nonce.
+///
+/// An access key is only locked while a nonce is taken, the transactions
+/// themselves are sent concurrently. One overtaken by a transaction with a
+/// higher nonce fails with a nonce error and is signed again.
+#[derive(Default)]
+pub struct NonceManager {
+    access_keys: Mutex<HashMap<PublicKey, Arc<tokio::sync::Mutex<Option<AccessKeyState>>>>>,
+    next_signer: AtomicUsize,
+}
+
+impl NonceManager {
+    /// Picks each of `signers` in turn to spread transactions across keys.
+    pub fn choose_signer<'signers>(
+        &self,
+        signers: &'signers [InMemorySigner],
+    ) -> &'signers InMemorySigner {
+        &signers[self.next_signer.fetch_add(1, Ordering::Relaxed) % signers.len()]
+    }
+
+    /// Waits until no other transaction is taking a nonce of the access key.
+    pub async fn lock(&self, public_key: &PublicKey) -> AccessKeyLock {
+        let access_key = self
+            .access_keys
+            .lock()
+            .unwrap()
+            .entry(public_key.clone())
+            .or_default()
+            .clone();
+
+        AccessKeyLock(access_key.lock_owned().await)
+    }
+}
+
+/// Exclusive use of an access key until dropped.
+pub struct AccessKeyLock(OwnedMutexGuard<Option<AccessKeyState>>);
+
+impl AccessKeyLock {
+    /// Returns the next nonce with a recent block hash, or `None` if the
+    /// access key has to be synced with the chain first.
+    pub fn next_nonce(&mut self) -> Option<(Nonce, CryptoHash)> {
+        let state = self
+            .0
+            .as_mut()
+            .filter(|state| state.synced_at.elapsed() < MAX_BLOCK_HASH_AGE)?;
+        state.nonce += 1;
+
+        Some((state.nonce, state.block_hash))
+    }
+
+    /// Records the nonce of the access key as of the block `block_hash`.
+    pub fn sync(&mut self, nonce: Nonce, block_hash: CryptoHash) {
+        *self.0 = Some(AccessKeyState {
+            nonce,
+            block_hash,
+            synced_at: Instant::now(),
+        });
+    }
+
+    /// Forgets the cached nonce, e.g. after a nonce error, so that the next
+    /// transaction syncs it again.
+    pub fn reset(&mut self) {
+        *self.0 = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_primitives::hash::CryptoHash;
+
+    use super::NonceManager;
+
+    fn signer(seed: &str) -> InMemorySigner {
+        InMemorySigner::from_seed("issuer.near".parse().unwrap(), KeyType::ED25519, seed)
+    }
+
+    #[tokio::test]
+    async fn hands_out_consecutive_nonces() {
+        let nonces = NonceManager::default();
+        let public_key = signer("a").public_key;
+
+        let mut access_key = nonces.lock(&public_key).await;
+        assert_eq!(access_key.next_nonce(), None);
+
+        access_key.sync(41, CryptoHash::default());
+        assert_eq!(access_key.next_nonce(), Some((42, CryptoHash::default())));
+        assert_eq!(access_key.next_nonce(), Some((43, CryptoHash::default())));
+        drop(access_key);
+
+        let mut access_key = nonces.lock(&public_key).await;
+        assert_eq!(access_key.next_nonce(), Some((44, CryptoHash::default())));
+
+        access_key.reset();
+        assert_eq!(access_key.next_nonce(), None);
+    }
+
+    #[tokio::test]
+    async fn locks_each_access_key() {
+        let nonces = NonceManager::default();
+        let first = signer("a").public_key;
+        let second = signer("b").public_key;
+
+        let _access_key = nonces.lock(&first).await;
+
+        // Another access key is available right away
+        nonces.lock(&second).await;
+        // The same access key waits for the lock to be dropped
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), nonces.lock(&first))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn chooses_signers_in_turn() {
+        let nonces = NonceManager::default();
+        let signers = [signer("a"), signer("b"), signer("c")];
+
+        let chosen: Vec<_> = (0..4)
+            .map(|_| nonces.choose_signer(&signers).public_key.clone())
+            .collect();
+
+        assert_eq!(
+            chosen,
+            [
+                signers[0].public_key.clone(),
+                signers[1].public_key.clone(),
+                signers[2].public_key.clone(),
+                signers[0].public_key.clone(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use errors::APIResult;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Records the API server keeps between requests, such as mint jobs.
+///
+/// Every write saves the whole store as a JSON file, so records survive a
+/// restart. A store without a file keeps its records in memory only. Records
+/// not written for longer than the store's time to live are evicted, which
+/// keeps the file small.
+pub struct Store<Record> {
+    path: Option<PathBuf>,
+    ttl: Option<Duration>,
+    records: Mutex<BTreeMap<String, Entry<Record>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<Record> {
+    record: Record,
+    /// Milliseconds since the Unix epoch.
+    updated_at: u64,
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl<Record> Store<Record>
+where
+    Record: Clone + Serialize + DeserializeOwned,
+{
+    pub fn in_memory() -> Self {
+        Store {
+            path: None,
+            ttl: None,
+            records: Mutex::default(),
+        }
+    }
+
+    /// Loads the records saved in `path`, which is created on the first write.
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let records = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(Store {
+            path: Some(path),
+            ttl: None,
+            records: Mutex::new(records),
+        })
+    }
+
+    /// Evicts the records not written for longer than `ttl`.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Store {
+            ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    fn is_expired(&self, entry: &Entry<Record>, now: u64) -> bool {
+        match self.ttl {
+            Some(ttl) => now.saturating_sub(entry.updated_at) >= ttl.as_millis() as u64,
+            None => false,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<Record> {
+        let records = self.records.lock().unwrap();
+        let entry = records.get(id)?;
+
+        (!self.is_expired(entry, now_ms())).then(|| entry.record.clone())
+    }
+
+    pub fn insert(&self, id: &str, record: Record) -> APIResult<()> {
+        let mut records = self.records.lock().unwrap();
+        records.insert(
+            id.to_string(),
+            Entry {
+                record,
+                updated_at: now_ms(),
+            },
+        );
+
+        self.save(&mut records)
+    }
+
+    /// Inserts `record` unless `id` is taken, in which case the record
+    /// already stored is returned instead.
+    pub fn try_insert(&self, id: &str, record: Record) -> APIResult<Option<Record>> {
+        let mut records = self.records.lock().unwrap();
+        let now = now_ms();
+        match records.get(id) {
+            Some(existing) if !self.is_expired(existing, now) => {
+                return Ok(Some(existing.record.clone()))
+            }
+            _ => {}
+        }
+        records.insert(
+            id.to_string(),
+            Entry {
+                record,
+                updated_at: now,
+            },
+        );
+
+        self.save(&mut records).map(|_| None)
+    }
+
+    /// Applies `update` to the record stored under `id`, if any, and saves
+    /// the result.
+    pub fn update<Value>(
+        &self,
+        id: &str,
+        update: impl FnOnce(&mut Record) -> Value,
+    ) -> APIResult<Option<Value>> {
+        let mut records = self.records.lock().unwrap();
+        let now = now_ms();
+        let value = match records.get_mut(id) {
+            Some(entry) if !self.is_expired(entry, now) => {
+                entry.updated_at = now;
+                update(&mut entry.record)
+            }
+            _ => return Ok(None),
+        };
+
+        self.save(&mut records).map(|_| Some(value))
+    }
+
+    pub fn remove(&self, id: &str) -> APIResult<()> {
+        let mut records = self.records.lock().unwrap();
+        records.remove(id);
+
+        self.save(&mut records)
+    }
+
+    /// Evicts the expired records, then writes the rest to the file.
+    fn save(&self, records: &mut BTreeMap<String, Entry<Record>>) -> APIResult<()> {
+        let now = now_ms();
+        records.retain(|_, entry| !self.is_expired(entry, now));
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write a copy first so that a crash can't leave a truncated file
+        let temporary_path = path.with_extension("tmp");
+        let saved = serde_json::to_vec(records)
+            .map_err(std::io::Error::from)
+            .and_then(|bytes| fs::write(&temporary_path, bytes))
+            .and_then(|_| fs::rename(&temporary_path, path));
+
+        saved.map_err(|error| {
+            eprintln!("{error:?}");
+            errors::APIError::StoreError {
+                path: path.display().to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Store;
+
+    #[test]
+    fn persists_records() {
+        let path = std::env::temp_dir().join(format!("store-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = Store::<u64>::open(&path).unwrap();
+        assert_eq!(store.get("a"), None);
+        store.insert("a", 1).unwrap();
+        store.insert("b", 2).unwrap();
+        store.insert("a", 3).unwrap();
+
+        let reopened = Store::<u64>::open(&path).unwrap();
+        assert_eq!(reopened.get("a"), Some(3));
+        assert_eq!(reopened.get("b"), Some(2));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_records_in_memory() {
+        let store = Store::in_memory();
+        store.insert("a", "record".to_string()).unwrap();
+
+        assert_eq!(store.get("a"), Some("record".to_string()));
+    }
+
+    #[test]
+    fn evicts_expired_records() {
+        let store = Store::in_memory().with_ttl(Duration::ZERO);
+        store.insert("a", 1).unwrap();
+        assert_eq!(store.get("a"), None);
+        // An expired record no longer takes its ID
+        assert_eq!(store.try_insert("a", 2).unwrap(), None);
+        assert!(store.records.lock().unwrap().is_empty());
+
+        let store = Store::in_memory().with_ttl(Duration::from_secs(60));
+        store.insert("a", 1).unwrap();
+        assert_eq!(store.get("a"), Some(1));
+    }
+
+    #[test]
+    fn inserts_only_new_records() {
+        let store = Store::in_memory();
+
+        assert_eq!(store.try_insert("a", 1).unwrap(), None);
+        assert_eq!(store.try_insert("a", 2).unwrap(), Some(1));
+        assert_eq!(store.get("a"), Some(1));
+
+        store.remove("a").unwrap();
+        assert_eq!(store.try_insert("a", 2).unwrap(), None);
+        assert_eq!(store.get("a"), Some(2));
+    }
+
+    #[test]
+    fn updates_records() {
+        let store = Store::in_memory();
+        store.insert("a", 1).unwrap();
+
+        assert_eq!(store.update("a", |record| *record += 1).unwrap(), Some(()));
+        assert_eq!(store.get("a"), Some(2));
+        assert_eq!(store.update("b", |record| *record += 1).unwrap(), None);
+        assert_eq!(store.get("b"), None);
+    }
+}
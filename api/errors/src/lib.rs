@@ -1,7 +1,8 @@
 pub use anyhow;
-use axum::response::IntoResponse;
+use axum::{http::StatusCode, response::IntoResponse};
+use serde_json::json;
 
-#[derive(thiserror::Error, Debug, serde::Serialize)]
+#[derive(thiserror::Error, Debug)]
 pub enum APIError {
     #[error("Invalid account ID provided. {account_id:?} does not exist.")]
     AccountNotFound {
@@ -9,41 +10,287 @@ pub enum APIError {
     },
     #[error("Invalid account ID provided. {account_id} did not pass validation.")]
     AccountInvalid { account_id: String },
-    #[error("Invalid private key provided. {private_key} did not pass validation.")]
-    PrivateKeyInvalid { private_key: String },
+    /// Only the position of the key is given, as no variant may hold secrets.
+    /// 0 is the main key of the issuing authority, followed by its key pool.
+    #[error(
+        "Invalid private key provided. Key {key_index} of the issuer did not pass validation."
+    )]
+    PrivateKeyInvalid { key_index: usize },
     #[error("Minting the certificate failed with error: {message}.")]
     MintFailure { message: String },
+    #[error("A token with this ID already exists.")]
+    TokenAlreadyExists,
     #[error("Invalidating the certificate failed with error: {message}.")]
     InvalidateFailure { message: String },
     #[error("Error parsing bytes: {bytes:?}.")]
     ParseError { bytes: Vec<u8> },
     #[error("Error deserializing string: {string}.")]
     DeserializationError { string: String },
+    #[error("No token with ID: {token_id}.")]
+    TokenNotFound { token_id: String },
     #[error("No metadata for token with ID: {token_id}.")]
     NoMetadataError { token_id: String },
     #[error("Extra metadata is invalid: {extra}.")]
     ExtraMetadataInvalid { extra: String },
-    #[error("Couldn't connect to database: {database_url}")]
-    DBConnectionError { database_url: String },
+    #[error("Issue date is invalid: {issued_at}.")]
+    IssuedAtInvalid { issued_at: String },
+    #[error("Couldn't connect to database.")]
+    DBConnectionError,
     #[error("Error executing query.")]
     DBQueryExecutionError,
     #[error("Certificate with ID: {token_id} is invalid.")]
     CertificateInvalid { token_id: String },
+    #[error("Recipient name does not match the commitment of certificate with ID: {token_id}.")]
+    RecipientNameMismatch { token_id: String },
+    #[error("Payload is invalid: {errors:?}.")]
+    InvalidPayload { errors: Vec<FieldError> },
+    #[error("Batch is invalid: {errors:?}.")]
+    InvalidBatch { errors: Vec<String> },
+    #[error("No batch with ID: {batch_id}.")]
+    BatchNotFound { batch_id: String },
+    #[error("Batch with ID: {batch_id} is still being minted.")]
+    BatchInProgress { batch_id: String },
+    #[error("No job with ID: {job_id}.")]
+    JobNotFound { job_id: String },
+    #[error("Couldn't save the store: {path}.")]
+    StoreError { path: String },
+    #[error("Idempotency key {key} was already used for a different request.")]
+    IdempotencyKeyMismatch { key: String },
+    #[error("A request with idempotency key {key} is still in progress.")]
+    IdempotencyKeyInUse { key: String },
+    #[error("Couldn't render the image of certificate with ID: {token_id}.")]
+    ImageRenderError { token_id: String },
+    #[error("The RPC request failed.")]
+    RpcError,
     #[error("Unauthorized request.")]
     Unauthorized,
     #[error("Something unexpected went wrong.")]
     ServerError,
 }
 
+/// Problem with a field of a request payload.
+#[derive(Debug, serde::Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl APIError {
+    /// Machine-readable identifier of the error, stable across releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            APIError::AccountNotFound { .. } => "account_not_found",
+            APIError::AccountInvalid { .. } => "account_invalid",
+            APIError::PrivateKeyInvalid { .. } => "private_key_invalid",
+            APIError::MintFailure { .. } => "mint_failed",
+            APIError::TokenAlreadyExists => "token_already_exists",
+            APIError::InvalidateFailure { .. } => "invalidate_failed",
+            APIError::ParseError { .. } => "parse_error",
+            APIError::DeserializationError { .. } => "deserialization_error",
+            APIError::TokenNotFound { .. } => "token_not_found",
+            APIError::NoMetadataError { .. } => "metadata_missing",
+            APIError::ExtraMetadataInvalid { .. } => "extra_metadata_invalid",
+            APIError::IssuedAtInvalid { .. } => "issued_at_invalid",
+            APIError::DBConnectionError => "database_unavailable",
+            APIError::DBQueryExecutionError => "database_query_failed",
+            APIError::CertificateInvalid { .. } => "certificate_invalid",
+            APIError::RecipientNameMismatch { .. } => "recipient_name_mismatch",
+            APIError::InvalidPayload { .. } => "invalid_payload",
+            APIError::InvalidBatch { .. } => "invalid_batch",
+            APIError::BatchNotFound { .. } => "batch_not_found",
+            APIError::BatchInProgress { .. } => "batch_in_progress",
+            APIError::JobNotFound { .. } => "job_not_found",
+            APIError::StoreError { .. } => "store_error",
+            APIError::IdempotencyKeyMismatch { .. } => "idempotency_key_mismatch",
+            APIError::IdempotencyKeyInUse { .. } => "idempotency_key_in_use",
+            APIError::ImageRenderError { .. } => "image_render_failed",
+            APIError::RpcError => "rpc_error",
+            APIError::Unauthorized => "unauthorized",
+            APIError::ServerError => "server_error",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            APIError::AccountInvalid { .. }
+            | APIError::RecipientNameMismatch { .. }
+            | APIError::InvalidPayload { .. }
+            | APIError::InvalidBatch { .. } => StatusCode::BAD_REQUEST,
+            APIError::Unauthorized => StatusCode::UNAUTHORIZED,
+            APIError::AccountNotFound { .. }
+            | APIError::TokenNotFound { .. }
+            | APIError::BatchNotFound { .. }
+            | APIError::JobNotFound { .. } => StatusCode::NOT_FOUND,
+            APIError::IdempotencyKeyMismatch { .. }
+            | APIError::IdempotencyKeyInUse { .. }
+            | APIError::BatchInProgress { .. }
+            | APIError::TokenAlreadyExists => StatusCode::CONFLICT,
+            APIError::CertificateInvalid { .. } => StatusCode::GONE,
+            APIError::RpcError
+            | APIError::MintFailure { .. }
+            | APIError::InvalidateFailure { .. }
+            | APIError::ParseError { .. }
+            | APIError::DeserializationError { .. }
+            | APIError::DBQueryExecutionError => StatusCode::BAD_GATEWAY,
+            APIError::DBConnectionError => StatusCode::SERVICE_UNAVAILABLE,
+            APIError::PrivateKeyInvalid { .. }
+            | APIError::NoMetadataError { .. }
+            | APIError::ExtraMetadataInvalid { .. }
+            | APIError::IssuedAtInvalid { .. }
+            | APIError::StoreError { .. }
+            | APIError::ImageRenderError { .. }
+            | APIError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Fields of the error which are safe to show to clients, or `null` if it
+    /// has none. Every variant is listed so that a new field is never exposed
+    /// by accident.
+    fn details(&self) -> serde_json::Value {
+        match self {
+            APIError::AccountNotFound { account_id } => json!({ "account_id": account_id }),
+            APIError::AccountInvalid { account_id } => json!({ "account_id": account_id }),
+            APIError::PrivateKeyInvalid { key_index } => json!({ "key_index": key_index }),
+            APIError::TokenNotFound { token_id }
+            | APIError::NoMetadataError { token_id }
+            | APIError::CertificateInvalid { token_id }
+            | APIError::RecipientNameMismatch { token_id }
+            | APIError::ImageRenderError { token_id } => json!({ "token_id": token_id }),
+            APIError::InvalidPayload { errors } => json!({ "errors": errors }),
+            APIError::InvalidBatch { errors } => json!({ "errors": errors }),
+            APIError::BatchNotFound { batch_id } | APIError::BatchInProgress { batch_id } => {
+                json!({ "batch_id": batch_id })
+            }
+            APIError::JobNotFound { job_id } => json!({ "job_id": job_id }),
+            APIError::IdempotencyKeyMismatch { key } | APIError::IdempotencyKeyInUse { key } => {
+                json!({ "key": key })
+            }
+            // Raw payloads, chain errors and server paths stay out of responses
+            APIError::MintFailure { .. }
+            | APIError::InvalidateFailure { .. }
+            | APIError::ParseError { .. }
+            | APIError::DeserializationError { .. }
+            | APIError::ExtraMetadataInvalid { .. }
+            | APIError::IssuedAtInvalid { .. }
+            | APIError::TokenAlreadyExists
+            | APIError::DBConnectionError
+            | APIError::DBQueryExecutionError
+            | APIError::StoreError { .. }
+            | APIError::RpcError
+            | APIError::Unauthorized
+            | APIError::ServerError => serde_json::Value::Null,
+        }
+    }
+}
+
 impl IntoResponse for APIError {
     fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            APIError::Unauthorized => axum::http::StatusCode::UNAUTHORIZED,
-            _ => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let body = serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "details": self.details(),
+        });
 
-        (status_code, format!("{self}")).into_response()
+        (self.status_code(), axum::Json(body)).into_response()
     }
 }
 
 pub type APIResult<SuccessValue> = Result<SuccessValue, APIError>;
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    use super::{APIError, FieldError};
+
+    async fn response(error: APIError) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn maps_errors_to_status_codes() {
+        for (error, status) in [
+            (
+                APIError::InvalidPayload { errors: vec![] },
+                StatusCode::BAD_REQUEST,
+            ),
+            (APIError::Unauthorized, StatusCode::UNAUTHORIZED),
+            (
+                APIError::TokenNotFound {
+                    token_id: "token".to_string(),
+                },
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                APIError::IdempotencyKeyMismatch {
+                    key: "key".to_string(),
+                },
+                StatusCode::CONFLICT,
+            ),
+            (
+                APIError::CertificateInvalid {
+                    token_id: "token".to_string(),
+                },
+                StatusCode::GONE,
+            ),
+            (APIError::TokenAlreadyExists, StatusCode::CONFLICT),
+            (APIError::RpcError, StatusCode::BAD_GATEWAY),
+            (
+                APIError::MintFailure {
+                    message: "Smart contract panicked".to_string(),
+                },
+                StatusCode::BAD_GATEWAY,
+            ),
+            (APIError::DBConnectionError, StatusCode::SERVICE_UNAVAILABLE),
+            (APIError::ServerError, StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let code = error.code();
+            let (actual_status, body) = response(error).await;
+
+            assert_eq!(actual_status, status, "{code}");
+            assert_eq!(body["code"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn serializes_errors_as_json() {
+        let (_, body) = response(APIError::InvalidPayload {
+            errors: vec![FieldError {
+                field: "program_link".to_string(),
+                message: "\"near\" is not an http(s) URL".to_string(),
+            }],
+        })
+        .await;
+        assert_eq!(body["code"], "invalid_payload");
+        assert!(body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Payload is invalid"));
+        assert_eq!(
+            body["details"],
+            serde_json::json!({
+                "errors": [{ "field": "program_link", "message": "\"near\" is not an http(s) URL" }],
+            })
+        );
+
+        let (_, body) = response(APIError::ParseError {
+            bytes: b"raw payload".to_vec(),
+        })
+        .await;
+        assert_eq!(body["details"], serde_json::Value::Null);
+
+        let (_, body) = response(APIError::ServerError).await;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": "server_error",
+                "message": "Something unexpected went wrong.",
+                "details": null,
+            })
+        );
+    }
+}
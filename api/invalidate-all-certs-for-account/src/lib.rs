@@ -1,47 +1,54 @@
+use std::sync::Arc;
+
 use axum::{
     debug_handler,
     extract::{Json, State},
     http::StatusCode,
 };
-use common::{SignerData, TGAS, YOCTO_NEAR};
+use common::{ChainClient, SignerData, TGAS, YOCTO_NEAR};
 use errors::APIResult;
 use near_primitives::{
     transaction::{Action, FunctionCallAction},
     views::FinalExecutionStatus,
 };
 
+/// Maximum number of certificates invalidated by a single transaction.
+const INVALIDATE_LIMIT: u64 = 20;
+
 #[derive(serde::Deserialize)]
 pub struct Payload {
     account_id: String,
 }
 
-async fn call_cert_invalidate(account_id: &str, signer_data: SignerData) -> APIResult<bool> {
-    let tokens = common::get_tokens_for_owner(account_id).await?;
-
-    let actions = tokens
-        .into_iter()
-        .map(|token| {
-            let method_name = "cert_invalidate".to_string();
-            let gas = 100 * TGAS;
-            let deposit = YOCTO_NEAR;
-            let args = serde_json::json!({"token_id": token.token_id})
-                .to_string()
-                .into_bytes();
-
-            Action::FunctionCall(FunctionCallAction {
-                method_name,
-                args,
-                gas,
-                deposit,
-            })
-        })
-        .collect();
-
-    match common::send_transaction_to_certs(actions, signer_data)
+async fn call_cert_invalidate_for_account(
+    chain_client: &dyn ChainClient,
+    account_id: &str,
+    from_index: Option<&str>,
+    signer_data: SignerData,
+) -> APIResult<Option<String>> {
+    let method_name = "cert_invalidate_for_account".to_string();
+    let gas = 300 * TGAS;
+    let deposit = YOCTO_NEAR;
+    let args = serde_json::json!({
+        "account_id": account_id,
+        "from_index": from_index,
+        "limit": INVALIDATE_LIMIT,
+    })
+    .to_string()
+    .into_bytes();
+
+    let action = Action::FunctionCall(FunctionCallAction {
+        method_name,
+        args,
+        gas,
+        deposit,
+    });
+
+    match chain_client
+        .send_transaction_to_certs(vec![action], signer_data)
         .await?
-        .status
     {
-        FinalExecutionStatus::SuccessValue(_) => Ok(true),
+        FinalExecutionStatus::SuccessValue(value) => common::deserialize_bytes(&value),
         FinalExecutionStatus::Failure(error) => Err(errors::APIError::InvalidateFailure {
             message: error.to_string(),
         }),
@@ -51,12 +58,38 @@ async fn call_cert_invalidate(account_id: &str, signer_data: SignerData) -> APIR
     }
 }
 
-#[debug_handler]
+async fn call_cert_invalidate(
+    chain_client: &dyn ChainClient,
+    account_id: &str,
+    signer_data: SignerData,
+) -> APIResult<bool> {
+    // The contract returns the position to resume from, so keep calling it
+    // until every certificate of the account has been visited
+    let mut from_index = None;
+    loop {
+        from_index = call_cert_invalidate_for_account(
+            chain_client,
+            account_id,
+            from_index.as_deref(),
+            signer_data.clone(),
+        )
+        .await?;
+
+        if from_index.is_none() {
+            break;
+        }
+    }
+
+    Ok(true)
+}
+
+#[debug_handler(state = common::AppData)]
 pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
     State(signer_data): State<SignerData>,
     Json(Payload { account_id }): Json<Payload>,
 ) -> APIResult<(StatusCode, String)> {
-    call_cert_invalidate(&account_id, signer_data).await?;
+    call_cert_invalidate(chain_client.as_ref(), &account_id, signer_data).await?;
 
     Ok((StatusCode::OK, "Invalidation successfull.".to_string()))
 }
@@ -1,16 +1,25 @@
+use std::sync::Arc;
+
 use axum::{
     debug_handler,
     extract::{Json, State},
     http::StatusCode,
 };
-use common::{SignerData, TGAS, YOCTO_NEAR};
+use common::{ChainClient, SignerData, TGAS, YOCTO_NEAR};
 use errors::APIResult;
 use near_primitives::{
     transaction::{Action, FunctionCallAction},
     views::FinalExecutionStatus,
 };
 
-async fn call_cert_invalidate(token_id: &str, signer_data: SignerData) -> APIResult<bool> {
+/// Panic message of the contract when `token_id` doesn't exist.
+const TOKEN_NOT_FOUND_PANIC: &str = "Token does not exist";
+
+async fn call_cert_invalidate(
+    chain_client: &dyn ChainClient,
+    token_id: &str,
+    signer_data: SignerData,
+) -> APIResult<bool> {
     let method_name = "cert_invalidate".to_string();
     let gas = 100 * TGAS;
     let deposit = YOCTO_NEAR;
@@ -25,14 +34,22 @@ async fn call_cert_invalidate(token_id: &str, signer_data: SignerData) -> APIRes
         deposit,
     });
 
-    match common::send_transaction_to_certs(vec![action], signer_data)
+    match chain_client
+        .send_transaction_to_certs(vec![action], signer_data)
         .await?
-        .status
     {
         FinalExecutionStatus::SuccessValue(_) => Ok(true),
-        FinalExecutionStatus::Failure(error) => Err(errors::APIError::InvalidateFailure {
-            message: error.to_string(),
-        }),
+        FinalExecutionStatus::Failure(error) => {
+            let message = error.to_string();
+
+            if message.contains(TOKEN_NOT_FOUND_PANIC) {
+                Err(errors::APIError::TokenNotFound {
+                    token_id: token_id.to_string(),
+                })
+            } else {
+                Err(errors::APIError::InvalidateFailure { message })
+            }
+        }
         _ => Err(errors::APIError::MintFailure {
             message: "Transaction is not yet completed.".to_string(),
         }),
@@ -44,12 +61,13 @@ pub struct Payload {
     token_id: String,
 }
 
-#[debug_handler]
+#[debug_handler(state = common::AppData)]
 pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
     State(signer_data): State<SignerData>,
     Json(Payload { token_id }): Json<Payload>,
 ) -> APIResult<(StatusCode, String)> {
-    call_cert_invalidate(&token_id, signer_data).await?;
+    call_cert_invalidate(chain_client.as_ref(), &token_id, signer_data).await?;
 
     Ok((StatusCode::OK, "Invalidation successfull.".to_string()))
 }
@@ -0,0 +1,63 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    Json,
+};
+use common::{ChainClient, MintJob, MintJobStatus, Store};
+use errors::APIResult;
+
+/// How long the transaction of a job may stay unknown to the RPC node.
+/// Transactions are included within seconds, so one still unknown after this
+/// was dropped.
+const UNKNOWN_TRANSACTION_DEADLINE: Duration = Duration::from_secs(10 * 60);
+
+/// Polls the transaction of a pending job and records its outcome.
+async fn update_mint_job(chain_client: &dyn ChainClient, job: &mut MintJob) -> APIResult<()> {
+    let Some(status) = chain_client
+        .transaction_status(&job.transaction_hash, &job.signer_id)
+        .await?
+    else {
+        if common::now_ms().saturating_sub(job.created_at)
+            > UNKNOWN_TRANSACTION_DEADLINE.as_millis() as u64
+        {
+            job.status = MintJobStatus::Expired;
+        }
+        return Ok(());
+    };
+
+    job.status = match mint_cert::mint_result(status) {
+        Ok(token) => MintJobStatus::Succeeded {
+            token: Box::new(token),
+        },
+        Err(errors::APIError::MintFailure { message }) => MintJobStatus::Failed { message },
+        Err(error @ errors::APIError::TokenAlreadyExists) => MintJobStatus::Failed {
+            message: error.to_string(),
+        },
+        Err(error) => return Err(error),
+    };
+
+    Ok(())
+}
+
+#[debug_handler(state = common::AppData)]
+pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
+    State(mint_jobs): State<Arc<Store<MintJob>>>,
+    Path(job_id): Path<String>,
+) -> APIResult<Json<MintJob>> {
+    let Some(mut job) = mint_jobs.get(&job_id) else {
+        return Err(errors::APIError::JobNotFound { job_id });
+    };
+
+    if let MintJobStatus::Pending = job.status {
+        update_mint_job(chain_client.as_ref(), &mut job).await?;
+
+        if !matches!(job.status, MintJobStatus::Pending) {
+            mint_jobs.insert(&job_id, job.clone())?;
+        }
+    }
+
+    Ok(Json(job))
+}
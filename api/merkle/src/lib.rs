@@ -0,0 +1,229 @@
+//! Builds the sha256 Merkle trees and inclusion proofs of off-chain
+//! certification batches, matching what `cert_verify_inclusion` checks
+//! on-chain.
+//!
+//! The root of a tree is anchored with `cert_anchor_batch`, and each
+//! certificate holder receives their record together with its proof.
+
+use near_certification_tools::{leaf_encoding, CertificationExtraMetadata};
+use near_sdk::{json_types::Base58CryptoHash, CryptoHash};
+use sha2::{Digest, Sha256};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+pub fn leaf_hash(record: &CertificationExtraMetadata) -> CryptoHash {
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(leaf_encoding(record))
+        .finalize()
+        .into()
+}
+
+fn node_hash(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Merkle tree over a batch of certification records.
+pub struct MerkleTree {
+    /// Tree levels, from the leaves up to the root.
+    levels: Vec<Vec<CryptoHash>>,
+}
+
+impl MerkleTree {
+    pub fn new(records: &[CertificationExtraMetadata]) -> Self {
+        assert!(!records.is_empty(), "Batch must not be empty");
+
+        let mut levels = vec![records.iter().map(leaf_hash).collect::<Vec<_>>()];
+
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node_hash(a, b),
+                    // A node without a sibling is promoted unchanged
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            levels.push(level);
+        }
+
+        Self { levels }
+    }
+
+    /// Number of records in the batch.
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Root to pass to `cert_anchor_batch`.
+    pub fn root(&self) -> Base58CryptoHash {
+        self.levels.last().unwrap()[0].into()
+    }
+
+    /// Inclusion proof of the record at `index`, to pass to
+    /// `cert_verify_inclusion`.
+    pub fn proof(&self, index: usize) -> Vec<Base58CryptoHash> {
+        assert!(index < self.len(), "Record index out of bounds");
+
+        let mut index = index;
+        let mut proof = Vec::new();
+
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push((*sibling).into());
+            }
+            index /= 2;
+        }
+
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_certification_tools::{CertificationContract, CertificationContractInitOptions};
+    use near_contract_standards::non_fungible_token::metadata::{
+        NFTContractMetadata, NFT_METADATA_SPEC,
+    };
+    use near_sdk::{
+        env,
+        test_utils::{accounts, VMContextBuilder},
+        testing_env, ONE_NEAR,
+    };
+
+    use super::*;
+
+    fn record(i: usize) -> CertificationExtraMetadata {
+        CertificationExtraMetadata {
+            authority_id: Some(accounts(0)),
+            authority_name: Some("NEAR University".to_string()),
+            program: Some("MOOC101".to_string()),
+            program_name: Some("Intro to NEAR".to_string()),
+            program_link: None,
+            program_start_date: None,
+            program_end_date: None,
+            original_recipient_id: Some(format!("student{i}.testnet").parse().unwrap()),
+            original_recipient_name: None,
+            original_recipient_name_commitment: None,
+            valid: true,
+            memo: None,
+            grade: None,
+            score: None,
+            max_score: None,
+            level: None,
+            credits: None,
+            supersedes: None,
+            superseded_by: None,
+            localized: None,
+        }
+    }
+
+    fn records(count: usize) -> Vec<CertificationExtraMetadata> {
+        (0..count).map(record).collect()
+    }
+
+    fn init_contract() -> CertificationContract {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CertificationContract::new(
+            accounts(0),
+            NFTContractMetadata {
+                spec: NFT_METADATA_SPEC.to_string(),
+                name: "Certifications".to_string(),
+                symbol: "CERT".to_string(),
+                icon: None,
+                base_uri: None,
+                reference: None,
+                reference_hash: None,
+            },
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+        contract.add_issuer(accounts(0), None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(ONE_NEAR)
+            .build());
+
+        contract
+    }
+
+    #[test]
+    fn test_single_record_root_is_leaf() {
+        let records = records(1);
+        let tree = MerkleTree::new(&records);
+
+        assert_eq!(tree.root(), leaf_hash(&records[0]).into());
+        assert!(tree.proof(0).is_empty());
+    }
+
+    #[test]
+    fn test_leaf_hash_is_pinned() {
+        // Changing the leaf encoding breaks the proofs of anchored batches
+        let hash: Base58CryptoHash = leaf_hash(&record(0)).into();
+
+        assert_eq!(
+            serde_json::to_value(hash).unwrap(),
+            "BDy5dTQvU9JAXHWEFWaG4itc2AycnPjqG3SgML1rfSbw"
+        );
+    }
+
+    #[test]
+    fn test_proofs_verify_on_chain() {
+        let mut contract = init_contract();
+
+        for count in [2, 3, 7, 8] {
+            let records = records(count);
+            let tree = MerkleTree::new(&records);
+            let batch_id = format!("batch-{count}");
+
+            contract.cert_anchor_batch(
+                batch_id.clone(),
+                tree.root(),
+                "MOOC101".to_string(),
+                count as u64,
+            );
+
+            for (index, record) in records.into_iter().enumerate() {
+                assert!(contract.cert_verify_inclusion(
+                    batch_id.clone(),
+                    record,
+                    tree.proof(index)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_foreign_record_does_not_verify() {
+        let mut contract = init_contract();
+        let tree = MerkleTree::new(&records(3));
+
+        contract.cert_anchor_batch("batch".to_string(), tree.root(), "MOOC101".to_string(), 3);
+
+        assert!(!contract.cert_verify_inclusion("batch".to_string(), record(3), tree.proof(0)));
+        assert!(!contract.cert_verify_inclusion("batch".to_string(), record(1), tree.proof(0)));
+    }
+}
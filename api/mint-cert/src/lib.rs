@@ -1,14 +1,35 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use axum::{
     self, debug_handler,
-    extract::{Json, State},
+    extract::{Extension, Json, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use common::{
+    ChainClient, IdempotentRequest, MintJob, MintJobStatus, SignerData, Store, NEAR, TGAS,
+};
+use errors::{APIResult, FieldError};
+use near_certification_tools::{
+    recipient_name_commitment_preimage, AccountId, CertificationExtraMetadata, LocalizedMetadata,
+    MintNFT, Token, TokenMetadata,
 };
-use common::{SignerData, NEAR, TGAS};
-use errors::APIResult;
-use near_certification_tools::{CertificationExtraMetadata, MintNFT, Token, TokenMetadata};
 use near_primitives::{
     transaction::{Action, FunctionCallAction},
     views::FinalExecutionStatus,
 };
+use sha2::{Digest, Sha256};
+
+/// Minimum number of random bytes in a recipient name salt, so that the
+/// commitment cannot be brute-forced from a list of likely names.
+const MIN_SALT_BYTES: usize = 16;
+
+/// Deposit attached to `nft_mint` to cover the storage of the certificate. The
+/// contract refunds the unused part.
+pub const MINT_DEPOSIT: u128 = NEAR / 5;
+
+/// Panic message of the contract when `token_id` is already minted.
+const TOKEN_EXISTS_PANIC: &str = "token_id must be unique";
 
 #[derive(serde::Deserialize)]
 pub struct MintPayloadDetails {
@@ -24,6 +45,16 @@ pub struct MintPayloadDetails {
     program_end_date: String,
     original_recipient_id: String,
     original_recipient_name: String,
+    /// When set, only a salted commitment to `original_recipient_name` is
+    /// stored on-chain. The recipient keeps the salt, at least 16 random
+    /// bytes in hex or base64.
+    original_recipient_name_salt: Option<String>,
+    grade: Option<String>,
+    score: Option<f64>,
+    max_score: Option<f64>,
+    level: Option<String>,
+    credits: Option<f64>,
+    localized: Option<BTreeMap<String, LocalizedMetadata>>,
 }
 
 #[derive(serde::Deserialize)]
@@ -31,33 +62,137 @@ pub struct MintPayload {
     details: MintPayloadDetails,
 }
 
-impl From<MintPayload> for MintNFT {
-    fn from(MintPayload { details }: MintPayload) -> MintNFT {
+impl TryFrom<MintPayload> for MintNFT {
+    type Error = errors::APIError;
+
+    fn try_from(MintPayload { details }: MintPayload) -> APIResult<MintNFT> {
+        details.try_into()
+    }
+}
+
+impl TryFrom<MintPayloadDetails> for MintNFT {
+    type Error = errors::APIError;
+
+    fn try_from(details: MintPayloadDetails) -> APIResult<MintNFT> {
         let token_id = uuid::Uuid::new_v4().to_string().replace("-", "");
 
-        MintNFT {
-            receiver_account_id: Some(details.original_recipient_id.parse().unwrap()),
+        details.into_mint_nft(token_id)
+    }
+}
+
+impl MintPayloadDetails {
+    /// Arguments of `nft_mint` minting the certificate as `token_id`.
+    pub fn into_mint_nft(self, token_id: String) -> APIResult<MintNFT> {
+        let mut errors = vec![];
+        let authority_id = check(
+            &mut errors,
+            "authority_id",
+            parse_account_id(&self.authority_id),
+        );
+        let original_recipient_id = check(
+            &mut errors,
+            "original_recipient_id",
+            parse_account_id(&self.original_recipient_id),
+        );
+        let issued_at = check(&mut errors, "issued_at", parse_timestamp(&self.issued_at));
+        let program_link = check(&mut errors, "program_link", check_url(&self.program_link));
+        let program_start_date = check(
+            &mut errors,
+            "program_start_date",
+            parse_timestamp(&self.program_start_date),
+        );
+        let program_end_date = match (
+            program_start_date,
+            check(
+                &mut errors,
+                "program_end_date",
+                parse_timestamp(&self.program_end_date),
+            ),
+        ) {
+            (Some(start), Some(end)) if end < start => check(
+                &mut errors,
+                "program_end_date",
+                Err("must not be before program_start_date".to_string()),
+            ),
+            (_, end) => end,
+        };
+        let original_recipient_name_salt = match &self.original_recipient_name_salt {
+            Some(salt) => check(
+                &mut errors,
+                "original_recipient_name_salt",
+                check_salt(salt),
+            ),
+            None => Some(()),
+        };
+
+        // Every invalid field has been reported, not just the first one
+        let (
+            Some(authority_id),
+            Some(original_recipient_id),
+            Some(issued_at),
+            Some(()),
+            Some(program_start_date),
+            Some(program_end_date),
+            Some(()),
+        ) = (
+            authority_id,
+            original_recipient_id,
+            issued_at,
+            program_link,
+            program_start_date,
+            program_end_date,
+            original_recipient_name_salt,
+        )
+        else {
+            return Err(errors::APIError::InvalidPayload { errors });
+        };
+
+        let (original_recipient_name, original_recipient_name_commitment) =
+            match self.original_recipient_name_salt {
+                Some(salt) => {
+                    let commitment: [u8; 32] = Sha256::digest(recipient_name_commitment_preimage(
+                        &self.original_recipient_name,
+                        &salt,
+                    ))
+                    .into();
+
+                    (None, Some(commitment.into()))
+                }
+                None => (Some(self.original_recipient_name), None),
+            };
+
+        Ok(MintNFT {
+            receiver_account_id: Some(original_recipient_id.clone()),
             certification_metadata: CertificationExtraMetadata {
-                authority_id: Some(details.authority_id.parse().unwrap()),
-                authority_name: Some(details.authority_name),
-                program: Some(details.program),
-                program_name: Some(details.program_name),
-                program_link: Some(details.program_link),
-                program_start_date: Some(details.program_start_date.parse::<u64>().unwrap().into()),
-                program_end_date: Some(details.program_end_date.parse::<u64>().unwrap().into()),
-                original_recipient_id: Some(details.original_recipient_id.parse().unwrap()),
-                original_recipient_name: Some(details.original_recipient_name),
+                authority_id: Some(authority_id),
+                authority_name: Some(self.authority_name),
+                program: Some(self.program),
+                program_name: Some(self.program_name),
+                program_link: Some(self.program_link),
+                program_start_date: Some(program_start_date.into()),
+                program_end_date: Some(program_end_date.into()),
+                original_recipient_id: Some(original_recipient_id),
+                original_recipient_name,
+                original_recipient_name_commitment,
                 memo: None,
                 valid: true,
+                grade: self.grade,
+                score: self.score,
+                max_score: self.max_score,
+                level: self.level,
+                credits: self.credits,
+                supersedes: None,
+                superseded_by: None,
+                localized: self.localized,
             },
             token_metadata: TokenMetadata {
-                title: Some(details.title),
-                description: Some(details.description),
+                title: Some(self.title),
+                description: Some(self.description),
                 media: Some(format!(
                     "https://certificates.near.university/api/cert/{token_id}.svg"
                 )),
                 copies: Some(1),
-                issued_at: Some(details.issued_at),
+                issued_at: Some(issued_at.to_string()),
                 media_hash: None,
                 expires_at: None,
                 starts_at: None,
@@ -68,41 +203,199 @@ impl From<MintPayload> for MintNFT {
             },
             token_id,
             memo: None,
+        })
+    }
+}
+
+/// Value of `result`, or `None` with its error recorded against `field`.
+fn check<Value>(
+    errors: &mut Vec<FieldError>,
+    field: &str,
+    result: Result<Value, String>,
+) -> Option<Value> {
+    match result {
+        Ok(value) => Some(value),
+        Err(message) => {
+            errors.push(FieldError {
+                field: field.to_string(),
+                message,
+            });
+            None
+        }
+    }
+}
+
+fn parse_account_id(account_id: &str) -> Result<AccountId, String> {
+    account_id
+        .parse()
+        .map_err(|_| format!("{account_id:?} is not a valid account ID"))
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    match url::Url::parse(url) {
+        Ok(url) if ["http", "https"].contains(&url.scheme()) => Ok(()),
+        _ => Err(format!("{url:?} is not an http(s) URL")),
+    }
+}
+
+/// Checks that a recipient name salt holds at least `MIN_SALT_BYTES` bytes,
+/// hex or (standard or URL-safe) base64 encoded.
+fn check_salt(salt: &str) -> Result<(), String> {
+    let bytes = if salt.chars().all(|c| c.is_ascii_hexdigit()) {
+        salt.len() / 2
+    } else if salt
+        .trim_end_matches('=')
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+/-_".contains(c))
+    {
+        salt.trim_end_matches('=').len() * 3 / 4
+    } else {
+        0
+    };
+
+    if bytes < MIN_SALT_BYTES {
+        return Err(format!(
+            "must be at least {MIN_SALT_BYTES} random bytes in hex or base64"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Milliseconds since the Unix epoch, given either as such or as an RFC 3339
+/// date.
+fn parse_timestamp(date: &str) -> Result<u64, String> {
+    if let Ok(timestamp) = date.parse() {
+        return Ok(timestamp);
+    }
+
+    match chrono::DateTime::parse_from_rfc3339(date) {
+        Ok(date_time) if date_time.timestamp_millis() >= 0 => {
+            Ok(date_time.timestamp_millis() as u64)
         }
+        _ => Err(format!(
+            "{date:?} is neither a timestamp in milliseconds nor an RFC 3339 date"
+        )),
     }
 }
 
-async fn call_mint(payload: MintNFT, signer_data: SignerData) -> APIResult<Token> {
+fn mint_action(payload: MintNFT) -> Action {
     let method_name = "nft_mint".to_string();
     let gas = 100 * TGAS;
-    let deposit = NEAR / 5;
+    let deposit = MINT_DEPOSIT;
     let args = serde_json::json!(payload).to_string().into_bytes();
 
-    let function_call = Action::FunctionCall(FunctionCallAction {
+    Action::FunctionCall(FunctionCallAction {
         method_name,
         args,
         gas,
         deposit,
-    });
+    })
+}
 
-    match common::send_transaction_to_certs(vec![function_call], signer_data)
-        .await?
-        .status
-    {
+/// Minted token of a completed mint transaction.
+pub fn mint_result(status: FinalExecutionStatus) -> APIResult<Token> {
+    match status {
         FinalExecutionStatus::SuccessValue(value) => common::deserialize_bytes(&value),
-        FinalExecutionStatus::Failure(error) => Err(errors::APIError::MintFailure {
-            message: error.to_string(),
-        }),
+        FinalExecutionStatus::Failure(error) => {
+            let message = error.to_string();
+
+            if message.contains(TOKEN_EXISTS_PANIC) {
+                Err(errors::APIError::TokenAlreadyExists)
+            } else {
+                Err(errors::APIError::MintFailure { message })
+            }
+        }
         _ => Err(errors::APIError::MintFailure {
             message: "Transaction is not yet completed.".to_string(),
         }),
     }
 }
 
-#[debug_handler]
+async fn call_mint(
+    chain_client: &dyn ChainClient,
+    payload: MintNFT,
+    signer_data: SignerData,
+) -> APIResult<Token> {
+    mint_result(
+        chain_client
+            .send_transaction_to_certs(vec![mint_action(payload)], signer_data)
+            .await?,
+    )
+}
+
+async fn start_mint_job(
+    chain_client: &dyn ChainClient,
+    mint_jobs: &Store<MintJob>,
+    payload: MintNFT,
+    signer_data: SignerData,
+) -> APIResult<MintJob> {
+    let signer_id = match signer_data.account_id.parse() {
+        Ok(signer_id) => signer_id,
+        Err(_) => {
+            return Err(errors::APIError::AccountInvalid {
+                account_id: signer_data.account_id,
+            })
+        }
+    };
+
+    let transaction_hash = chain_client
+        .broadcast_transaction_to_certs(vec![mint_action(payload)], signer_data)
+        .await?;
+
+    let job = MintJob {
+        job_id: uuid::Uuid::new_v4().simple().to_string(),
+        transaction_hash,
+        signer_id,
+        created_at: common::now_ms(),
+        status: MintJobStatus::Pending,
+    };
+    mint_jobs.insert(&job.job_id, job.clone())?;
+
+    Ok(job)
+}
+
+#[derive(serde::Deserialize)]
+pub struct MintQuery {
+    /// Return a pending `MintJob` right after broadcasting the transaction
+    /// instead of waiting for the minted token.
+    #[serde(rename = "async", default)]
+    is_async: bool,
+}
+
+#[debug_handler(state = common::AppData)]
 pub async fn handler(
+    State(chain_client): State<Arc<dyn ChainClient>>,
     State(signer_data): State<SignerData>,
-    Json(payload): Json<MintPayload>,
-) -> APIResult<axum::Json<Token>> {
-    Ok(axum::Json(call_mint(payload.into(), signer_data).await?))
+    State(mint_jobs): State<Arc<Store<MintJob>>>,
+    Query(MintQuery { is_async }): Query<MintQuery>,
+    idempotent_request: Option<Extension<IdempotentRequest>>,
+    Json(MintPayload { details }): Json<MintPayload>,
+) -> APIResult<Response> {
+    // A retry with the same idempotency key mints the same token
+    let mint_nft = match idempotent_request {
+        Some(Extension(idempotent_request)) => {
+            let token_id = idempotent_request.token_id();
+
+            // The interrupted run may have minted it already
+            if idempotent_request.resumed {
+                if let Some(token) = common::find_token(chain_client.as_ref(), &token_id).await? {
+                    return Ok(axum::Json(token).into_response());
+                }
+            }
+
+            details.into_mint_nft(token_id)?
+        }
+        None => details.try_into()?,
+    };
+
+    if is_async {
+        let job = start_mint_job(chain_client.as_ref(), &mint_jobs, mint_nft, signer_data).await?;
+
+        return Ok((StatusCode::ACCEPTED, axum::Json(job)).into_response());
+    }
+
+    let token = call_mint(chain_client.as_ref(), mint_nft, signer_data).await?;
+
+    Ok(axum::Json(token).into_response())
 }
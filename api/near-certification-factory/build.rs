@@ -0,0 +1,23 @@
+use std::{env, fs, path::Path};
+
+// Embeds the compiled certification contract. Build it first with
+// `./build.sh`; without it the factory is built with empty code and refuses
+// to deploy instances.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let code_path = Path::new(&manifest_dir)
+        .join("../target/wasm32-unknown-unknown/release/near_certification_tools.wasm");
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("near_certification_tools.wasm");
+
+    println!("cargo:rerun-if-changed={}", code_path.display());
+
+    let code = fs::read(&code_path).unwrap_or_else(|_| {
+        println!(
+            "cargo:warning=Certification contract code not found at {}",
+            code_path.display()
+        );
+        vec![]
+    });
+
+    fs::write(out_path, code).unwrap();
+}
@@ -0,0 +1,5 @@
+#!/usr/bin/env bash
+
+# The certification contract must be built first, it gets embedded in the factory
+cargo build --package near-certification-tools --target wasm32-unknown-unknown --release
+cargo build --package near-certification-factory --target wasm32-unknown-unknown --release
@@ -0,0 +1,7 @@
+#!/usr/bin/env bash
+
+near deploy \
+  --wasmFile ../target/wasm32-unknown-unknown/release/near_certification_factory.wasm \
+  --accountId "$1" \
+  --initFunction new \
+  --initArgs "{\"owner_id\": \"$2\"}"
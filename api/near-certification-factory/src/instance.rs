@@ -0,0 +1,32 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::{Base58CryptoHash, U64},
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Registry entry of a certification contract deployed by the factory.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CertificationInstance {
+    /// Owner of the deployed certification contract.
+    pub owner_id: AccountId,
+
+    /// Hash of the certification contract code the instance is running.
+    pub code_hash: Base58CryptoHash,
+
+    /// Deployment timestamp, i.e. number of non-leap-nanoseconds since
+    /// January 1, 1970 0:00:00 UTC.
+    ///
+    /// Compatible with `env::block_timestamp()`.
+    pub created_at: U64,
+}
+
+/// Options forwarded to the `new` method of the certification contract. The
+/// factory itself is always set as the upgrade authority.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CertificationInstanceOptions {
+    pub can_transfer: bool,
+    pub can_invalidate: bool,
+}
@@ -0,0 +1,477 @@
+use near_contract_standards::non_fungible_token::metadata::NFTContractMetadata;
+use near_contract_tools::{impl_ownership, ownership::Ownership};
+use near_sdk::{
+    assert_one_yocto,
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedMap,
+    env, ext_contract,
+    json_types::{Base58CryptoHash, Base64VecU8, U128},
+    near_bindgen, require, serde_json, AccountId, Balance, Gas, PanicOnDefault, Promise,
+    PromiseResult,
+};
+
+pub use crate::instance::{CertificationInstance, CertificationInstanceOptions};
+
+mod instance;
+mod storage_key;
+
+use crate::storage_key::StorageKey;
+
+/// Compiled certification contract, see `build.rs`.
+#[cfg(not(test))]
+const CERTIFICATION_CODE: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/near_certification_tools.wasm"));
+#[cfg(test)]
+const CERTIFICATION_CODE: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Storage reserved for the initial state of a new instance, on top of the
+/// contract code.
+const INSTANCE_STATE_STORAGE: u64 = 10_000;
+
+const GAS_FOR_INIT: Gas = Gas(50_000_000_000_000);
+const GAS_FOR_UPGRADE: Gas = Gas(70_000_000_000_000);
+const GAS_FOR_CALLBACK: Gas = Gas(10_000_000_000_000);
+/// Maximum number of upgrades started by `upgrade_instances`, also the
+/// default, so that their gas fits in a single transaction.
+const MAX_UPGRADE_LIMIT: u64 = 3;
+/// Maximum number of entries returned by `get_instances`, also the default.
+const MAX_INSTANCES_LIMIT: u64 = 100;
+
+#[allow(dead_code)]
+#[ext_contract(ext_certification)]
+trait Certification {
+    fn upgrade(&mut self, code: Base64VecU8, migrate: bool);
+}
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct CertificationFactory {
+    pub(crate) instances: UnorderedMap<AccountId, CertificationInstance>,
+    pub(crate) ownership: Ownership,
+}
+
+fn certification_code() -> &'static [u8] {
+    require!(
+        !CERTIFICATION_CODE.is_empty(),
+        "Factory was built without certification contract code"
+    );
+    CERTIFICATION_CODE
+}
+
+fn is_promise_success() -> bool {
+    require!(
+        env::promise_results_count() == 1,
+        "Expected a single promise result"
+    );
+    matches!(env::promise_result(0), PromiseResult::Successful(_))
+}
+
+#[near_bindgen]
+impl CertificationFactory {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        // Only allow the contract to be initialized once
+        require!(!env::state_exists(), "Already initialized");
+
+        Self {
+            instances: UnorderedMap::new(StorageKey::Instances),
+            ownership: Ownership::new(StorageKey::Ownership, owner_id),
+        }
+    }
+
+    /// Hash of the embedded certification contract code.
+    pub fn code_hash(&self) -> Base58CryptoHash {
+        env::sha256_array(certification_code()).into()
+    }
+
+    /// Deposit required by `create_instance`.
+    pub fn get_instance_deposit(&self) -> U128 {
+        U128(
+            Balance::from(certification_code().len() as u64 + INSTANCE_STATE_STORAGE)
+                * env::storage_byte_cost(),
+        )
+    }
+
+    pub fn get_instance(&self, account_id: AccountId) -> Option<CertificationInstance> {
+        self.instances.get(&account_id)
+    }
+
+    pub fn get_instance_count(&self) -> U128 {
+        U128(self.instances.len() as u128)
+    }
+
+    pub fn get_instances(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<(AccountId, CertificationInstance)> {
+        let from_index = from_index.map(|i| i.0 as usize).unwrap_or(0);
+        let limit = limit
+            .unwrap_or(MAX_INSTANCES_LIMIT)
+            .min(MAX_INSTANCES_LIMIT);
+
+        self.instances
+            .iter()
+            .skip(from_index)
+            .take(limit as usize)
+            .collect()
+    }
+
+    #[payable]
+    pub fn create_instance(
+        &mut self,
+        name: String,
+        owner_id: AccountId,
+        metadata: NFTContractMetadata,
+        options: CertificationInstanceOptions,
+    ) -> Promise {
+        // Force owner
+        self.ownership.require_owner();
+
+        // Only direct sub-accounts of the factory
+        require!(!name.contains('.'), "Invalid instance name");
+        let account_id: AccountId = format!("{name}.{}", env::current_account_id())
+            .parse()
+            .unwrap_or_else(|_| env::panic_str("Invalid instance name"));
+
+        require!(
+            self.instances.get(&account_id).is_none(),
+            "Instance already exists"
+        );
+
+        // Validate metadata parameter
+        metadata.assert_valid();
+
+        let deposit = env::attached_deposit();
+        let required_deposit = self.get_instance_deposit().0;
+        require!(
+            deposit >= required_deposit,
+            format!("Attached deposit must be at least {required_deposit} yoctoNEAR")
+        );
+
+        let args = serde_json::json!({
+            "owner_id": owner_id,
+            "metadata": metadata,
+            "options": {
+                "can_transfer": options.can_transfer,
+                "can_invalidate": options.can_invalidate,
+                "upgrade_authority_id": env::current_account_id(),
+            },
+        })
+        .to_string()
+        .into_bytes();
+
+        Promise::new(account_id.clone())
+            .create_account()
+            .transfer(deposit)
+            .deploy_contract(certification_code().to_vec())
+            .function_call("new".to_string(), args, 0, GAS_FOR_INIT)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_CALLBACK)
+                    .on_instance_created(
+                        account_id,
+                        owner_id,
+                        env::predecessor_account_id(),
+                        U128(deposit),
+                    ),
+            )
+    }
+
+    #[private]
+    pub fn on_instance_created(
+        &mut self,
+        account_id: AccountId,
+        owner_id: AccountId,
+        payer_id: AccountId,
+        deposit: U128,
+    ) -> bool {
+        if !is_promise_success() {
+            // The failed batch returns the deposit to the factory
+            Promise::new(payer_id).transfer(deposit.0);
+            return false;
+        }
+
+        self.instances.insert(
+            &account_id,
+            &CertificationInstance {
+                owner_id,
+                code_hash: self.code_hash(),
+                created_at: env::block_timestamp().into(),
+            },
+        );
+
+        true
+    }
+
+    /// Upgrades instances running outdated code to the embedded certification
+    /// contract code. Returns the number of upgrades started.
+    pub fn upgrade_instances(
+        &mut self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+        migrate: bool,
+    ) -> u64 {
+        // Force owner
+        self.ownership.require_owner();
+
+        let code_hash = self.code_hash();
+        let limit = limit.unwrap_or(MAX_UPGRADE_LIMIT).min(MAX_UPGRADE_LIMIT);
+        let outdated: Vec<AccountId> = self
+            .get_instances(from_index, Some(limit))
+            .into_iter()
+            .filter(|(_, instance)| instance.code_hash != code_hash)
+            .map(|(account_id, _)| account_id)
+            .collect();
+
+        for account_id in &outdated {
+            ext_certification::ext(account_id.clone())
+                .with_static_gas(GAS_FOR_UPGRADE)
+                .upgrade(certification_code().to_vec().into(), migrate)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_CALLBACK)
+                        .on_instance_upgraded(account_id.clone(), code_hash),
+                );
+        }
+
+        outdated.len() as u64
+    }
+
+    #[private]
+    pub fn on_instance_upgraded(
+        &mut self,
+        account_id: AccountId,
+        code_hash: Base58CryptoHash,
+    ) -> bool {
+        if !is_promise_success() {
+            return false;
+        }
+
+        if let Some(instance) = self.instances.get(&account_id) {
+            self.instances.insert(
+                &account_id,
+                &CertificationInstance {
+                    code_hash,
+                    ..instance
+                },
+            );
+        }
+
+        true
+    }
+}
+
+impl_ownership!(CertificationFactory, ownership);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::non_fungible_token::metadata::{
+        NFTContractMetadata, NFT_METADATA_SPEC,
+    };
+    use near_sdk::{
+        env,
+        test_utils::{accounts, get_created_receipts, VMContextBuilder},
+        testing_env, AccountId, PromiseResult, RuntimeFeesConfig, VMConfig,
+    };
+
+    use crate::{CertificationFactory, CertificationInstanceOptions};
+
+    const INSTANCE_DEPOSIT: u128 = 5_000_000_000_000_000_000_000_000;
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id("factory.near".parse().unwrap())
+            .account_balance(14500000000000000000000000)
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn sample_metadata_contract() -> NFTContractMetadata {
+        NFTContractMetadata {
+            name: "Organization Certification Issuer".to_string(),
+            symbol: "XOCI".to_string(),
+            spec: NFT_METADATA_SPEC.to_string(),
+            reference: None,
+            reference_hash: None,
+            base_uri: None,
+            icon: None,
+        }
+    }
+
+    fn sample_options() -> CertificationInstanceOptions {
+        CertificationInstanceOptions {
+            can_transfer: false,
+            can_invalidate: true,
+        }
+    }
+
+    fn init_factory() -> (VMContextBuilder, CertificationFactory) {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let factory = CertificationFactory::new(accounts(0));
+
+        (context, factory)
+    }
+
+    fn set_promise_result(context: &VMContextBuilder, result: PromiseResult) {
+        testing_env!(
+            context.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    #[test]
+    fn create_instance() {
+        let (mut context, mut factory) = init_factory();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(INSTANCE_DEPOSIT)
+            .build());
+        factory.create_instance(
+            "school".to_string(),
+            accounts(1),
+            sample_metadata_contract(),
+            sample_options(),
+        );
+
+        // Instance deployment and callback
+        assert_eq!(get_created_receipts().len(), 2);
+        assert_eq!(
+            get_created_receipts()[0].receiver_id,
+            "school.factory.near".parse::<AccountId>().unwrap()
+        );
+        assert!(factory
+            .get_instance("school.factory.near".parse().unwrap())
+            .is_none());
+
+        context.predecessor_account_id("factory.near".parse().unwrap());
+        set_promise_result(&context, PromiseResult::Successful(vec![]));
+        assert!(factory.on_instance_created(
+            "school.factory.near".parse().unwrap(),
+            accounts(1),
+            accounts(0),
+            INSTANCE_DEPOSIT.into(),
+        ));
+
+        let instance = factory
+            .get_instance("school.factory.near".parse().unwrap())
+            .unwrap();
+        assert_eq!(instance.owner_id, accounts(1));
+        assert_eq!(instance.code_hash, factory.code_hash());
+        assert_eq!(factory.get_instance_count().0, 1);
+        assert_eq!(factory.get_instances(None, None).len(), 1);
+    }
+
+    #[test]
+    fn create_instance_failure_refunds() {
+        let (mut context, mut factory) = init_factory();
+
+        context.predecessor_account_id("factory.near".parse().unwrap());
+        set_promise_result(&context, PromiseResult::Failed);
+        assert!(!factory.on_instance_created(
+            "school.factory.near".parse().unwrap(),
+            accounts(1),
+            accounts(0),
+            INSTANCE_DEPOSIT.into(),
+        ));
+
+        // Deposit refund
+        assert_eq!(get_created_receipts().len(), 1);
+        assert_eq!(get_created_receipts()[0].receiver_id, accounts(0));
+        assert_eq!(factory.get_instance_count().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit must be at least")]
+    fn create_instance_insufficient_deposit() {
+        let (mut context, mut factory) = init_factory();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        factory.create_instance(
+            "school".to_string(),
+            accounts(1),
+            sample_metadata_contract(),
+            sample_options(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instance name")]
+    fn create_instance_nested_name() {
+        let (mut context, mut factory) = init_factory();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(INSTANCE_DEPOSIT)
+            .build());
+        factory.create_instance(
+            "school.nested".to_string(),
+            accounts(1),
+            sample_metadata_contract(),
+            sample_options(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner only")]
+    fn create_instance_not_owner() {
+        let (mut context, mut factory) = init_factory();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(INSTANCE_DEPOSIT)
+            .predecessor_account_id(accounts(1))
+            .build());
+        factory.create_instance(
+            "school".to_string(),
+            accounts(1),
+            sample_metadata_contract(),
+            sample_options(),
+        );
+    }
+
+    #[test]
+    fn upgrade_outdated_instances() {
+        let (mut context, mut factory) = init_factory();
+
+        context.predecessor_account_id("factory.near".parse().unwrap());
+        set_promise_result(&context, PromiseResult::Successful(vec![]));
+        for name in ["a.factory.near", "b.factory.near"] {
+            factory.on_instance_created(
+                name.parse().unwrap(),
+                accounts(1),
+                accounts(0),
+                INSTANCE_DEPOSIT.into(),
+            );
+        }
+
+        // Pretend the first instance was deployed from older code
+        let outdated: AccountId = "a.factory.near".parse().unwrap();
+        set_promise_result(&context, PromiseResult::Successful(vec![]));
+        factory.on_instance_upgraded(outdated.clone(), [0u8; 32].into());
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        assert_eq!(factory.upgrade_instances(None, None, true), 1);
+
+        // Upgrade call and callback
+        assert_eq!(get_created_receipts().len(), 2);
+        assert_eq!(get_created_receipts()[0].receiver_id, outdated);
+
+        context.predecessor_account_id("factory.near".parse().unwrap());
+        set_promise_result(&context, PromiseResult::Successful(vec![]));
+        assert!(factory.on_instance_upgraded(outdated.clone(), factory.code_hash()));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        assert_eq!(factory.upgrade_instances(None, None, true), 0);
+    }
+}
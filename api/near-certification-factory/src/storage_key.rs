@@ -0,0 +1,10 @@
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    BorshStorageKey,
+};
+
+#[derive(BorshSerialize, BorshStorageKey)]
+pub enum StorageKey {
+    Instances,
+    Ownership,
+}
@@ -0,0 +1,169 @@
+//! Example of a contract gating an action on a certification: accounts can
+//! only join a club if they hold a valid certification for its program.
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedSet,
+    env, ext_contract, near_bindgen, AccountId, Gas, PanicOnDefault, Promise, PromiseError,
+};
+
+const GAS_FOR_CHECK: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_CALLBACK: Gas = Gas(10_000_000_000_000);
+
+#[allow(dead_code)]
+#[ext_contract(ext_certification)]
+trait Certification {
+    fn cert_has_valid(&self, account_id: AccountId, program: String) -> bool;
+}
+
+#[derive(BorshSerialize, near_sdk::BorshStorageKey)]
+enum StorageKey {
+    Members,
+}
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct CertifiedClub {
+    certification_contract_id: AccountId,
+    program: String,
+    members: UnorderedSet<AccountId>,
+}
+
+#[near_bindgen]
+impl CertifiedClub {
+    #[init]
+    pub fn new(certification_contract_id: AccountId, program: String) -> Self {
+        Self {
+            certification_contract_id,
+            program,
+            members: UnorderedSet::new(StorageKey::Members),
+        }
+    }
+
+    pub fn is_member(&self, account_id: AccountId) -> bool {
+        self.members.contains(&account_id)
+    }
+
+    /// Adds the caller to the members if they hold a valid certification for
+    /// the program. Resolves to whether they joined.
+    pub fn join(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+
+        ext_certification::ext(self.certification_contract_id.clone())
+            .with_static_gas(GAS_FOR_CHECK)
+            .cert_has_valid(account_id.clone(), self.program.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_CALLBACK)
+                    .on_certification_checked(account_id),
+            )
+    }
+
+    #[private]
+    pub fn on_certification_checked(
+        &mut self,
+        account_id: AccountId,
+        #[callback_result] has_valid: Result<bool, PromiseError>,
+    ) -> bool {
+        if !matches!(has_valid, Ok(true)) {
+            return false;
+        }
+
+        self.members.insert(&account_id);
+
+        true
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::{
+        mock::VmAction,
+        serde_json,
+        test_utils::{accounts, get_created_receipts, VMContextBuilder},
+        testing_env, PromiseResult, RuntimeFeesConfig, VMConfig,
+    };
+
+    use super::*;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id("club.near".parse().unwrap())
+            .predecessor_account_id(accounts(1));
+        builder
+    }
+
+    fn init_club() -> (VMContextBuilder, CertifiedClub) {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let club = CertifiedClub::new("certs.near".parse().unwrap(), "CS101".to_string());
+
+        (context, club)
+    }
+
+    fn set_promise_result(context: &mut VMContextBuilder, result: PromiseResult) {
+        testing_env!(
+            context
+                .predecessor_account_id("club.near".parse().unwrap())
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    #[test]
+    fn join_checks_certification() {
+        let (_, mut club) = init_club();
+
+        club.join();
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, "certs.near".parse().unwrap());
+        let VmAction::FunctionCall {
+            function_name,
+            args,
+            ..
+        } = &receipts[0].actions[0]
+        else {
+            panic!("Expected a function call");
+        };
+        assert_eq!(function_name, "cert_has_valid");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(args).unwrap(),
+            serde_json::json!({ "account_id": accounts(1), "program": "CS101" }),
+        );
+        assert_eq!(receipts[1].receiver_id, "club.near".parse().unwrap());
+    }
+
+    #[test]
+    fn join_with_valid_certification() {
+        let (mut context, mut club) = init_club();
+
+        set_promise_result(&mut context, PromiseResult::Successful(b"true".to_vec()));
+        assert!(club.on_certification_checked(accounts(1), Ok(true)));
+        assert!(club.is_member(accounts(1)));
+    }
+
+    #[test]
+    fn join_without_valid_certification() {
+        let (mut context, mut club) = init_club();
+
+        set_promise_result(&mut context, PromiseResult::Successful(b"false".to_vec()));
+        assert!(!club.on_certification_checked(accounts(1), Ok(false)));
+        assert!(!club.is_member(accounts(1)));
+    }
+
+    #[test]
+    fn join_with_failed_check() {
+        let (mut context, mut club) = init_club();
+
+        set_promise_result(&mut context, PromiseResult::Failed);
+        assert!(!club.on_certification_checked(accounts(1), Err(PromiseError::Failed)));
+        assert!(!club.is_member(accounts(1)));
+    }
+}
@@ -0,0 +1,31 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::{Base58CryptoHash, U64},
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Batch of off-chain certification records anchored by its Merkle root.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AnchoredBatch {
+    /// Root of the sha256 Merkle tree of the batch records, see `merkle.rs`.
+    pub merkle_root: Base58CryptoHash,
+
+    /// Entity-specific program identifier shared by the batch records.
+    ///
+    /// For example: "CS101"
+    pub program: String,
+
+    /// Number of records in the batch.
+    pub count: u64,
+
+    /// NEAR account ID of the issuer who anchored the batch.
+    pub issuer_id: AccountId,
+
+    /// Anchoring timestamp, i.e. number of non-leap-nanoseconds since
+    /// January 1, 1970 0:00:00 UTC.
+    ///
+    /// Compatible with `env::block_timestamp()`.
+    pub anchored_at: U64,
+}
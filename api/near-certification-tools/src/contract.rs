@@ -1,30 +1,51 @@
 pub use init::CertificationContractInitOptions;
 use near_contract_standards::non_fungible_token::{
-    metadata::NFTContractMetadata, NonFungibleToken,
+    metadata::NFTContractMetadata, NonFungibleToken, TokenId,
 };
 use near_contract_tools::{impl_ownership, ownership::Ownership, rbac::Rbac};
 use near_sdk::{
     assert_one_yocto,
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::LazyOption,
+    collections::{LazyOption, LookupMap, Vector},
     env,
     json_types::*,
-    near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise,
+    near_bindgen, require,
+    serde::{Deserialize, Serialize},
+    serde_json, AccountId, Balance, BorshStorageKey, CryptoHash, Promise,
 };
 
+mod anchor;
+mod endorse;
 mod init;
 mod invalidate;
 mod mint;
 mod nft;
 mod permissions;
+mod recipient;
+mod storage;
+mod supersede;
+mod upgrade;
+mod verify;
+
+use crate::{
+    batch::AnchoredBatch,
+    endorsement::CertificationEndorsement,
+    event::{CertificationEventLogData, CreateEventLog},
+    issuer::IssuerGrant,
+    revocation::CertificationRevocation,
+    storage::CertificationStorage,
+};
 
-#[derive(BorshSerialize, BorshStorageKey)]
+#[derive(BorshSerialize, BorshStorageKey, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
 pub enum Role {
     Issuer,
+    Endorser,
+    Upgrader,
 }
 
 #[near_bindgen]
-#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+#[derive(BorshDeserialize, BorshSerialize)]
 pub struct CertificationContract {
     pub(crate) tokens: NonFungibleToken,
     pub(crate) metadata: LazyOption<NFTContractMetadata>,
@@ -32,6 +53,27 @@ pub struct CertificationContract {
     pub(crate) can_invalidate: bool,
     pub(crate) ownership: Ownership,
     pub(crate) rbac: Rbac<Role>,
+    pub(crate) revocations: Vector<CertificationRevocation>,
+    pub(crate) endorsements: LookupMap<TokenId, Vec<CertificationEndorsement>>,
+    pub(crate) issuer_grants: LookupMap<AccountId, IssuerGrant>,
+    pub(crate) anchored_batches: LookupMap<String, AnchoredBatch>,
+    pub(crate) token_storage: LookupMap<TokenId, CertificationStorage>,
+    pub(crate) withdrawal_reserve: Balance,
+}
+
+// Not derived with `PanicOnDefault`: outside of Wasm, `env::panic_str` can't
+// unwind out of the mocked host and aborts the tests instead of failing them.
+impl Default for CertificationContract {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        env::panic_str("The contract is not initialized");
+        #[cfg(not(target_arch = "wasm32"))]
+        panic!("The contract is not initialized");
+    }
+}
+
+fn metadata_hash(metadata: &NFTContractMetadata) -> Base58CryptoHash {
+    env::sha256_array(serde_json::to_string(metadata).unwrap().as_bytes()).into()
 }
 
 #[near_bindgen]
@@ -59,26 +101,69 @@ impl CertificationContract {
         // Force verification
         assert_one_yocto();
 
+        let old_metadata_hash = self.metadata.get().map(|metadata| metadata_hash(&metadata));
+
         self.metadata.set(&metadata);
+
+        self.create_event_log(CertificationEventLogData::SetMetadata {
+            old_metadata_hash,
+            new_metadata_hash: metadata_hash(&metadata),
+        })
+        .emit();
+    }
+
+    pub fn get_withdrawal_reserve(&self) -> U128 {
+        self.withdrawal_reserve.into()
+    }
+
+    /// Sets the balance kept on top of the storage cost by withdrawals, e.g.
+    /// to cover upcoming mints.
+    #[payable]
+    pub fn set_withdrawal_reserve(&mut self, reserve: U128) {
+        // Force owner
+        self.ownership.require_owner();
+        // Force verification
+        assert_one_yocto();
+
+        self.withdrawal_reserve = reserve.into();
+
+        self.create_event_log(CertificationEventLogData::SetWithdrawalReserve { reserve })
+            .emit();
     }
 
     pub fn get_max_withdrawal(&self) -> U128 {
-        U128::from(env::account_balance() - env::storage_byte_cost() * env::storage_usage() as u128)
+        U128::from(
+            (env::account_balance() - env::storage_byte_cost() * env::storage_usage() as u128)
+                .saturating_sub(self.withdrawal_reserve),
+        )
     }
 
     #[payable]
     pub fn withdraw(&mut self, amount: U128) -> Promise {
+        let owner_id = self.ownership.owner.as_ref().unwrap().to_owned();
+
+        self.withdraw_to(owner_id, amount)
+    }
+
+    #[payable]
+    pub fn withdraw_to(&mut self, beneficiary: AccountId, amount: U128) -> Promise {
         // Force owner
         self.ownership.require_owner();
         // Force verification
         assert_one_yocto();
 
-        let amount = amount.into();
+        let amount: Balance = amount.into();
         let max = self.get_max_withdrawal().into();
 
         require!(amount <= max, "Insufficient balance");
 
-        Promise::new(self.ownership.owner.as_ref().unwrap().to_owned()).transfer(amount)
+        self.create_event_log(CertificationEventLogData::Withdraw {
+            recipient_id: beneficiary.clone(),
+            amount: amount.into(),
+        })
+        .emit();
+
+        Promise::new(beneficiary).transfer(amount)
     }
 
     #[payable]
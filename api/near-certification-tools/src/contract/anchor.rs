@@ -0,0 +1,84 @@
+use near_contract_standards::non_fungible_token::refund_deposit;
+use near_sdk::{json_types::Base58CryptoHash, near_bindgen};
+
+use crate::{
+    batch::AnchoredBatch,
+    contract::*,
+    event::{CertificationEventLogData, CreateEventLog},
+    merkle,
+    metadata::CertificationExtraMetadata,
+    utils::assert_nonzero_deposit,
+};
+
+#[near_bindgen]
+impl CertificationContract {
+    pub fn cert_anchored_batch(&self, batch_id: String) -> Option<AnchoredBatch> {
+        self.anchored_batches.get(&batch_id)
+    }
+
+    #[payable]
+    pub fn cert_anchor_batch(
+        &mut self,
+        batch_id: String,
+        merkle_root: Base58CryptoHash,
+        program: String,
+        count: u64,
+    ) {
+        require!(count > 0, "Batch must not be empty");
+        // Access control, every record of the batch counts against the quota
+        self.require_issuer_mint(count);
+        // Issuer pays for storage
+        assert_nonzero_deposit();
+
+        require!(
+            self.anchored_batches.get(&batch_id).is_none(),
+            "Batch already anchored"
+        );
+
+        let initial_storage_usage = env::storage_usage();
+        let issuer_id = env::predecessor_account_id();
+
+        self.anchored_batches.insert(
+            &batch_id,
+            &AnchoredBatch {
+                merkle_root,
+                program,
+                count,
+                issuer_id: issuer_id.clone(),
+                anchored_at: env::block_timestamp().into(),
+            },
+        );
+
+        self.create_event_log(CertificationEventLogData::AnchorBatch {
+            issuer_id,
+            batch_id,
+            merkle_root,
+            count,
+        })
+        .emit();
+
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    /// Returns `true` if `leaf` is one of the records of an anchored batch,
+    /// given its Merkle `proof` (sibling hashes from the leaf up to the root).
+    /// The record must be of the program the batch was anchored for.
+    pub fn cert_verify_inclusion(
+        &self,
+        batch_id: String,
+        leaf: CertificationExtraMetadata,
+        proof: Vec<Base58CryptoHash>,
+    ) -> bool {
+        let Some(batch) = self.anchored_batches.get(&batch_id) else {
+            return false;
+        };
+
+        if leaf.program.as_deref() != Some(batch.program.as_str()) {
+            return false;
+        }
+
+        let proof: Vec<CryptoHash> = proof.into_iter().map(Into::into).collect();
+
+        merkle::verify_inclusion(&batch.merkle_root.into(), merkle::leaf_hash(&leaf), &proof)
+    }
+}
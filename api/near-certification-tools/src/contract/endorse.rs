@@ -0,0 +1,85 @@
+use near_contract_standards::non_fungible_token::{refund_deposit, TokenId};
+use near_sdk::{assert_one_yocto, near_bindgen, Balance};
+
+use crate::{
+    contract::*,
+    endorsement::CertificationEndorsement,
+    event::{CertificationEventLogData, CreateEventLog},
+    utils::assert_nonzero_deposit,
+};
+
+#[near_bindgen]
+impl CertificationContract {
+    pub fn cert_endorsements(&self, token_id: TokenId) -> Vec<CertificationEndorsement> {
+        self.endorsements.get(&token_id).unwrap_or_default()
+    }
+
+    #[payable]
+    pub fn cert_endorse(&mut self, token_id: TokenId, statement: String) {
+        // Access control
+        self.rbac.require_role(&Role::Endorser);
+        // Endorser pays for storage
+        assert_nonzero_deposit();
+
+        require!(
+            self.tokens.owner_by_id.contains_key(&token_id),
+            "Token does not exist"
+        );
+        require!(!statement.trim().is_empty(), "Statement must not be empty");
+
+        let initial_storage_usage = env::storage_usage();
+        let endorser_id = env::predecessor_account_id();
+
+        let mut endorsements = self.cert_endorsements(token_id.clone());
+        // An endorser can only have a single endorsement per token
+        endorsements.retain(|endorsement| endorsement.endorser_id != endorser_id);
+        endorsements.push(CertificationEndorsement {
+            endorser_id: endorser_id.clone(),
+            statement: statement.clone(),
+            endorsed_at: env::block_timestamp().into(),
+        });
+        self.endorsements.insert(&token_id, &endorsements);
+
+        self.create_event_log(CertificationEventLogData::Endorse {
+            endorser_id,
+            token_id,
+            statement,
+        })
+        .emit();
+
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    #[payable]
+    pub fn cert_withdraw_endorsement(&mut self, token_id: TokenId) {
+        // Force verification
+        assert_one_yocto();
+
+        let initial_storage_usage = env::storage_usage();
+        let endorser_id = env::predecessor_account_id();
+
+        let mut endorsements = self.cert_endorsements(token_id.clone());
+        let count = endorsements.len();
+        endorsements.retain(|endorsement| endorsement.endorser_id != endorser_id);
+        require!(endorsements.len() < count, "Endorsement does not exist");
+
+        if endorsements.is_empty() {
+            self.endorsements.remove(&token_id);
+        } else {
+            self.endorsements.insert(&token_id, &endorsements);
+        }
+
+        // Refund the storage paid for by the endorser
+        let freed_bytes = initial_storage_usage.saturating_sub(env::storage_usage());
+        let refund = env::storage_byte_cost() * freed_bytes as Balance;
+        if refund > 0 {
+            Promise::new(endorser_id.clone()).transfer(refund);
+        }
+
+        self.create_event_log(CertificationEventLogData::EndorsementWithdraw {
+            endorser_id,
+            token_id,
+        })
+        .emit();
+    }
+}
@@ -1,19 +1,47 @@
-use crate::{contract::*, storage_key::StorageKey};
+use crate::{
+    contract::*,
+    event::{CertificationEventLogData, CreateEventLog},
+    storage_key::StorageKey,
+};
 use near_contract_standards::non_fungible_token::{
     metadata::NFTContractMetadata, NonFungibleToken,
 };
 use near_sdk::{
-    collections::LazyOption,
+    collections::{LazyOption, LookupMap, Vector},
     env, near_bindgen, require,
     serde::{Deserialize, Serialize},
-    AccountId,
+    AccountId, IntoStorageKey,
 };
 
+/// Version of the contract state layout, stored next to the state so that
+/// `migrate` knows which layout to read. Bump it along with a new branch in
+/// `migrate` whenever `CertificationContract` changes.
+///
+/// Version 0 is the layout before revocations, endorsements, issuer grants,
+/// anchored batches and storage records, which predates the stored version.
+const SCHEMA_VERSION: u8 = 1;
+
+fn read_schema_version() -> u8 {
+    env::storage_read(&StorageKey::SchemaVersion.into_storage_key())
+        .and_then(|version| version.first().copied())
+        .unwrap_or(0)
+}
+
+fn write_schema_version() {
+    env::storage_write(
+        &StorageKey::SchemaVersion.into_storage_key(),
+        &[SCHEMA_VERSION],
+    );
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct CertificationContractInitOptions {
     pub can_transfer: bool,
     pub can_invalidate: bool,
+    /// Account allowed to upgrade the contract code in addition to the owner,
+    /// e.g. the factory which deployed the contract.
+    pub upgrade_authority_id: Option<AccountId>,
 }
 
 #[near_bindgen]
@@ -30,6 +58,13 @@ impl CertificationContract {
         // Validate metadata parameter
         metadata.assert_valid();
 
+        let mut rbac = Rbac::new(StorageKey::Rbac);
+        if let Some(upgrade_authority_id) = options.upgrade_authority_id {
+            rbac.add_role(&upgrade_authority_id, &Role::Upgrader);
+        }
+
+        write_schema_version();
+
         Self {
             tokens: NonFungibleToken::new(
                 StorageKey::NonFungibleToken,
@@ -42,32 +77,63 @@ impl CertificationContract {
             can_transfer: options.can_transfer,
             can_invalidate: options.can_invalidate,
             ownership: Ownership::new(StorageKey::Ownership, owner_id),
-            rbac: Rbac::new(StorageKey::Rbac),
+            rbac,
+            revocations: Vector::new(StorageKey::Revocations),
+            endorsements: LookupMap::new(StorageKey::Endorsements),
+            issuer_grants: LookupMap::new(StorageKey::IssuerGrants),
+            anchored_batches: LookupMap::new(StorageKey::AnchoredBatches),
+            token_storage: LookupMap::new(StorageKey::TokenStorage),
+            withdrawal_reserve: 0,
         }
     }
 
     #[private]
     #[init(ignore_state)]
     pub fn migrate() -> Self {
+        let contract = match read_schema_version() {
+            0 => Self::migrate_from_v0(),
+            // Already current, e.g. an upgrade without state changes
+            SCHEMA_VERSION => return env::state_read().unwrap(),
+            _ => env::panic_str("Unknown schema version"),
+        };
+
+        write_schema_version();
+
+        contract
+            .create_event_log(CertificationEventLogData::Migrate {})
+            .emit();
+
+        contract
+    }
+}
+
+impl CertificationContract {
+    fn migrate_from_v0() -> Self {
         #[derive(BorshDeserialize)]
         struct OldSchema {
             pub tokens: NonFungibleToken,
             pub metadata: LazyOption<NFTContractMetadata>,
             pub can_transfer: bool,
             pub can_invalidate: bool,
+            pub ownership: Ownership,
+            pub rbac: Rbac<Role>,
         }
 
         let old: OldSchema = env::state_read().unwrap();
 
-        let owner_id = old.tokens.owner_id.to_owned();
-
         Self {
             tokens: old.tokens,
             metadata: old.metadata,
             can_transfer: old.can_transfer,
             can_invalidate: old.can_invalidate,
-            ownership: Ownership::new(StorageKey::Ownership, owner_id),
-            rbac: Rbac::new(StorageKey::Rbac),
+            ownership: old.ownership,
+            rbac: old.rbac,
+            revocations: Vector::new(StorageKey::Revocations),
+            endorsements: LookupMap::new(StorageKey::Endorsements),
+            issuer_grants: LookupMap::new(StorageKey::IssuerGrants),
+            anchored_batches: LookupMap::new(StorageKey::AnchoredBatches),
+            token_storage: LookupMap::new(StorageKey::TokenStorage),
+            withdrawal_reserve: 0,
         }
     }
 }
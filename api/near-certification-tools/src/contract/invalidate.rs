@@ -1,7 +1,7 @@
 use near_contract_standards::non_fungible_token::{
     events::NftBurn, metadata::TokenMetadata, TokenId,
 };
-use near_sdk::{assert_one_yocto, near_bindgen, serde_json};
+use near_sdk::{assert_one_yocto, near_bindgen, require, serde_json, AccountId, Balance};
 
 use crate::{
     contract::*,
@@ -9,6 +9,9 @@ use crate::{
     metadata::CertificationExtraMetadata,
 };
 
+/// Maximum number of entries returned by `cert_revocations`, also the default.
+const MAX_REVOCATIONS_LIMIT: u64 = 100;
+
 #[near_bindgen]
 impl CertificationContract {
     pub fn cert_is_valid(&self, token_id: TokenId) -> bool {
@@ -27,6 +30,27 @@ impl CertificationContract {
         .valid
     }
 
+    pub fn cert_revocation_count(&self) -> U128 {
+        U128(self.revocations.len() as u128)
+    }
+
+    pub fn cert_revocations(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<CertificationRevocation> {
+        let from_index = from_index.map(|i| i.0 as u64).unwrap_or(0);
+        let limit = limit
+            .unwrap_or(MAX_REVOCATIONS_LIMIT)
+            .min(MAX_REVOCATIONS_LIMIT);
+
+        self.revocations
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
     #[payable]
     pub fn cert_invalidate(&mut self, token_id: TokenId, memo: Option<String>) {
         self.assert_can_invalidate();
@@ -35,17 +59,71 @@ impl CertificationContract {
         // Force verification
         assert_one_yocto();
 
+        self.internal_invalidate(&token_id, memo);
+    }
+
+    /// Invalidates the certifications of `account_id` from position
+    /// `from_index` of its token list, visiting at most `limit` of them, and
+    /// returns the position to resume from, or `None` once every certification
+    /// has been visited.
+    #[payable]
+    pub fn cert_invalidate_for_account(
+        &mut self,
+        account_id: AccountId,
+        memo: Option<String>,
+        from_index: Option<U128>,
+        limit: u64,
+    ) -> Option<U128> {
+        self.assert_can_invalidate();
+        // Force owner only
+        self.ownership.require_owner();
+        // Force verification
+        assert_one_yocto();
+
+        require!(limit > 0, "Limit must be greater than zero");
+
+        let token_ids = self
+            .tokens
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(&account_id))?;
+
+        let from_index = from_index.map(|i| i.0 as u64).unwrap_or(0);
+        // Invalidation leaves tokens in place, so positions stay stable
+        // between calls
+        let page: Vec<TokenId> = token_ids
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect();
+        let next_index = from_index + page.len() as u64;
+
+        for token_id in &page {
+            if self.cert_is_valid(token_id.clone()) {
+                self.internal_invalidate(token_id, memo.clone());
+            }
+        }
+
+        (next_index < token_ids.len()).then(|| U128(next_index.into()))
+    }
+
+    pub(crate) fn internal_invalidate(&mut self, token_id: &TokenId, memo: Option<String>) {
         let lookup = self.tokens.token_metadata_by_id.as_mut().unwrap();
 
-        let metadata = lookup.get(&token_id).expect("Token does not exist");
+        let metadata = lookup.get(token_id).expect("Token does not exist");
 
         let certification_metadata =
             serde_json::from_str::<CertificationExtraMetadata>(&metadata.extra.unwrap()).unwrap();
 
+        // Invalidation is final, so there is nothing more to record
+        if !certification_metadata.valid {
+            return;
+        }
+
         let recipient_id = certification_metadata.original_recipient_id.clone();
 
         lookup.insert(
-            &token_id,
+            token_id,
             &TokenMetadata {
                 extra: Some(
                     CertificationExtraMetadata {
@@ -58,6 +136,13 @@ impl CertificationContract {
             },
         );
 
+        self.revocations.push(&CertificationRevocation {
+            token_id: token_id.to_owned(),
+            revoked_at: env::block_timestamp().into(),
+            reason: memo.clone(),
+            deleted: false,
+        });
+
         self.create_event_log(CertificationEventLogData::Invalidate {
             token_id: token_id.to_owned(),
             recipient_id,
@@ -66,8 +151,15 @@ impl CertificationContract {
         .emit();
     }
 
+    /// Deletes a certification, refunding the storage paid for it to its
+    /// payer, or to `refund_account_id` if given.
     #[payable]
-    pub fn cert_delete(&mut self, token_id: TokenId, memo: Option<String>) {
+    pub fn cert_delete(
+        &mut self,
+        token_id: TokenId,
+        memo: Option<String>,
+        refund_account_id: Option<AccountId>,
+    ) {
         // Disallow deletion if invalidation is disallowed (deletion is the stronger action)
         self.assert_can_invalidate();
         // Force owner only
@@ -75,37 +167,57 @@ impl CertificationContract {
         // Force verification
         assert_one_yocto();
 
+        let initial_storage_usage = env::storage_usage();
+
         // Remove approval
-        self.tokens.approvals_by_id.as_mut().map(|approvals_by_id| {
+        if let Some(approvals_by_id) = self.tokens.approvals_by_id.as_mut() {
             approvals_by_id.remove(&token_id);
-        });
+        }
 
         let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap();
 
         // Remove enumeration
-        self.tokens
-            .tokens_per_owner
-            .as_mut()
-            .map(|tokens_per_owner| {
-                tokens_per_owner.get(&owner_id).as_mut().map(|token_ids| {
-                    token_ids.remove(&token_id);
-                    if token_ids.len() == 0 {
-                        tokens_per_owner.remove(&owner_id);
-                    } else {
-                        tokens_per_owner.insert(&owner_id, &token_ids);
-                    }
-                });
-            });
+        if let Some(tokens_per_owner) = self.tokens.tokens_per_owner.as_mut() {
+            if let Some(mut token_ids) = tokens_per_owner.get(&owner_id) {
+                token_ids.remove(&token_id);
+                if token_ids.is_empty() {
+                    tokens_per_owner.remove(&owner_id);
+                } else {
+                    tokens_per_owner.insert(&owner_id, &token_ids);
+                }
+            }
+        }
 
         // Remove metadata
-        self.tokens
-            .token_metadata_by_id
-            .as_mut()
-            .map(|by_id| by_id.remove(&token_id));
+        if let Some(by_id) = self.tokens.token_metadata_by_id.as_mut() {
+            by_id.remove(&token_id);
+        }
+
+        // Remove endorsements
+        self.endorsements.remove(&token_id);
 
         // Remove from owners map
         self.tokens.owner_by_id.remove(&token_id);
 
+        // Refund storage, at most what was paid for (e.g. endorsements are
+        // paid for by endorsers)
+        if let Some(storage) = self.token_storage.remove(&token_id) {
+            let freed_bytes = initial_storage_usage.saturating_sub(env::storage_usage());
+            let refund = env::storage_byte_cost() * storage.bytes.min(freed_bytes) as Balance;
+
+            if refund > 0 {
+                Promise::new(refund_account_id.unwrap_or(storage.payer_id)).transfer(refund);
+            }
+        }
+
+        // Recorded after the refund, as the contract pays for the entry
+        self.revocations.push(&CertificationRevocation {
+            token_id: token_id.clone(),
+            revoked_at: env::block_timestamp().into(),
+            reason: memo.clone(),
+            deleted: true,
+        });
+
         // Emit NFT burn event
         NftBurn {
             owner_id: &owner_id,
@@ -2,10 +2,13 @@ use crate::{
     contract::*,
     event::{CertificationEventLogData, CreateEventLog},
     metadata::CertificationExtraMetadata,
+    storage::CertificationStorage,
     utils::assert_nonzero_deposit,
 };
 
-use near_contract_standards::non_fungible_token::{metadata::TokenMetadata, Token, TokenId};
+use near_contract_standards::non_fungible_token::{
+    events::NftMint, metadata::TokenMetadata, refund_deposit_to_account, Token, TokenId,
+};
 use near_sdk::{near_bindgen, require, AccountId};
 
 #[near_bindgen]
@@ -20,20 +23,46 @@ impl CertificationContract {
         memo: Option<String>,
     ) -> Token {
         // Access control
-        self.rbac.require_role(&Role::Issuer);
+        self.require_issuer_mint(1);
         // Force verification
         assert_nonzero_deposit();
 
-        // We are using the extra field for standard, type-safe custom metadata (not user-defined)
         require!(
-            token_metadata.extra == None,
-            "Specify extra metadata in certification_metadata parameter"
+            certification_metadata.supersedes.is_none()
+                && certification_metadata.superseded_by.is_none(),
+            "Use cert_supersede to link certifications"
         );
 
         let to_account_id = match receiver_account_id {
             Some(r) => r,
             None => self.tokens.owner_id.clone(),
         };
+
+        self.internal_mint_certification(
+            token_id,
+            to_account_id,
+            token_metadata,
+            certification_metadata,
+            memo,
+        )
+    }
+
+    pub(crate) fn internal_mint_certification(
+        &mut self,
+        token_id: TokenId,
+        to_account_id: AccountId,
+        token_metadata: TokenMetadata,
+        certification_metadata: CertificationExtraMetadata,
+        memo: Option<String>,
+    ) -> Token {
+        // We are using the extra field for standard, type-safe custom metadata (not user-defined)
+        require!(
+            token_metadata.extra.is_none(),
+            "Specify extra metadata in certification_metadata parameter"
+        );
+
+        certification_metadata.assert_valid();
+
         let combined_metadata = TokenMetadata {
             extra: Some(certification_metadata.to_json()),
             ..token_metadata
@@ -48,8 +77,32 @@ impl CertificationContract {
         })
         .emit();
 
-        // internal_mint manages storage cost refunding
-        self.tokens
-            .internal_mint(token_id, to_account_id, Some(combined_metadata))
+        let initial_storage_usage = env::storage_usage();
+        let payer_id = env::predecessor_account_id();
+
+        let token = self.tokens.internal_mint_with_refund(
+            token_id,
+            to_account_id,
+            Some(combined_metadata),
+            None,
+        );
+
+        // Record the storage paid for the token, including the record itself,
+        // so that it can be refunded when the token is deleted
+        let mut storage = CertificationStorage { payer_id, bytes: 0 };
+        self.token_storage.insert(&token.token_id, &storage);
+        storage.bytes = env::storage_usage() - initial_storage_usage;
+        self.token_storage.insert(&token.token_id, &storage);
+
+        refund_deposit_to_account(storage.bytes, storage.payer_id);
+
+        NftMint {
+            owner_id: &token.owner_id,
+            token_ids: &[&token.token_id],
+            memo: None,
+        }
+        .emit();
+
+        token
     }
 }
@@ -1,16 +1,110 @@
 use near_sdk::near_bindgen;
 
-use crate::contract::*;
+use crate::{
+    contract::*,
+    event::{CertificationEventLogData, CreateEventLog},
+    issuer::{IssuerGrant, IssuerGrantView},
+};
 
 #[near_bindgen]
 impl CertificationContract {
-    pub fn add_issuer(&mut self, account_id: AccountId) {
+    pub fn add_issuer(
+        &mut self,
+        account_id: AccountId,
+        expires_at: Option<U64>,
+        mint_quota: Option<u64>,
+    ) {
         self.ownership.require_owner();
         self.rbac.add_role(&account_id, &Role::Issuer);
+        self.create_event_log(CertificationEventLogData::RoleAdd {
+            role: Role::Issuer,
+            account_id: account_id.clone(),
+        })
+        .emit();
+
+        // Granting again resets the minted count
+        if expires_at.is_some() || mint_quota.is_some() {
+            self.issuer_grants.insert(
+                &account_id,
+                &IssuerGrant {
+                    expires_at,
+                    mint_quota,
+                    minted: 0,
+                },
+            );
+        } else {
+            self.issuer_grants.remove(&account_id);
+        }
     }
 
     pub fn remove_issuer(&mut self, account_id: AccountId) {
         self.ownership.require_owner();
         self.rbac.remove_role(&account_id, &Role::Issuer);
+        self.issuer_grants.remove(&account_id);
+        self.create_event_log(CertificationEventLogData::RoleRemove {
+            role: Role::Issuer,
+            account_id,
+        })
+        .emit();
+    }
+
+    pub fn get_issuer_grant(&self, account_id: AccountId) -> Option<IssuerGrantView> {
+        if !self.rbac.has_role(&account_id, &Role::Issuer) {
+            return None;
+        }
+
+        Some(match self.issuer_grants.get(&account_id) {
+            Some(grant) => IssuerGrantView {
+                expires_at: grant.expires_at,
+                remaining_quota: grant.remaining_quota(),
+            },
+            None => IssuerGrantView {
+                expires_at: None,
+                remaining_quota: None,
+            },
+        })
+    }
+
+    pub fn add_endorser(&mut self, account_id: AccountId) {
+        self.ownership.require_owner();
+        self.rbac.add_role(&account_id, &Role::Endorser);
+        self.create_event_log(CertificationEventLogData::RoleAdd {
+            role: Role::Endorser,
+            account_id,
+        })
+        .emit();
+    }
+
+    pub fn remove_endorser(&mut self, account_id: AccountId) {
+        self.ownership.require_owner();
+        self.rbac.remove_role(&account_id, &Role::Endorser);
+        self.create_event_log(CertificationEventLogData::RoleRemove {
+            role: Role::Endorser,
+            account_id,
+        })
+        .emit();
+    }
+
+    /// Requires the predecessor to be an issuer within the limits of their
+    /// grant, and counts `count` certifications against their quota.
+    pub(crate) fn require_issuer_mint(&mut self, count: u64) {
+        self.rbac.require_role(&Role::Issuer);
+
+        let issuer_id = env::predecessor_account_id();
+        let Some(mut grant) = self.issuer_grants.get(&issuer_id) else {
+            return;
+        };
+
+        require!(
+            !grant.is_expired(env::block_timestamp()),
+            "Issuer grant has expired"
+        );
+        require!(
+            !matches!(grant.remaining_quota(), Some(remaining_quota) if remaining_quota < count),
+            "Issuer mint quota exhausted"
+        );
+
+        grant.minted += count;
+        self.issuer_grants.insert(&issuer_id, &grant);
     }
 }
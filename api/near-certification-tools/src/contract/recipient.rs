@@ -0,0 +1,26 @@
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::near_bindgen;
+
+use crate::{contract::*, metadata::CertificationExtraMetadata};
+
+#[near_bindgen]
+impl CertificationContract {
+    /// Returns `true` if `name` is the recipient name committed to by a
+    /// particular certification, given the `salt` kept by the recipient.
+    pub fn cert_verify_recipient_name(
+        &self,
+        token_id: TokenId,
+        name: String,
+        salt: String,
+    ) -> bool {
+        let Some(commitment) = self
+            .certification_metadata(&token_id)
+            .and_then(|metadata| metadata.original_recipient_name_commitment)
+        else {
+            return false;
+        };
+
+        CryptoHash::from(commitment)
+            == CertificationExtraMetadata::recipient_name_commitment(&name, &salt)
+    }
+}
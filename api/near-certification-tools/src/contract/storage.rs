@@ -0,0 +1,82 @@
+use near_contract_standards::non_fungible_token::{metadata::TokenMetadata, TokenId};
+use near_sdk::{borsh::BorshSerialize, near_bindgen, AccountId, StorageUsage};
+
+use crate::{contract::*, metadata::CertificationExtraMetadata, storage::CertificationStorage};
+
+/// Storage overhead of each record (`storage_num_extra_bytes_record`).
+const RECORD_BYTES: StorageUsage = 40;
+/// Single-byte collection prefix, i.e. a `StorageKey` variant.
+const PREFIX_BYTES: StorageUsage = 1;
+/// Collection prefix of an owner's token set, the Borsh of
+/// `TokensPerOwner { account_hash }` followed by an element or index tag.
+const TOKENS_PER_OWNER_PREFIX_BYTES: StorageUsage = 1 + 4 + 32 + 1;
+
+fn borsh_len(value: &impl BorshSerialize) -> StorageUsage {
+    value.try_to_vec().unwrap().len() as StorageUsage
+}
+
+#[near_bindgen]
+impl CertificationContract {
+    pub fn cert_storage(&self, token_id: TokenId) -> Option<CertificationStorage> {
+        self.token_storage.get(&token_id)
+    }
+
+    /// Returns the deposit required to mint a certification with `nft_mint`
+    /// using the same arguments, when called by `payer_id` (the contract owner
+    /// by default).
+    pub fn cert_storage_cost_estimate(
+        &self,
+        token_id: TokenId,
+        receiver_account_id: Option<AccountId>,
+        token_metadata: TokenMetadata,
+        certification_metadata: CertificationExtraMetadata,
+        payer_id: Option<AccountId>,
+    ) -> U128 {
+        let to_account_id = receiver_account_id.unwrap_or_else(|| self.tokens.owner_id.clone());
+        let payer_id = payer_id.unwrap_or_else(|| self.tokens.owner_id.clone());
+        let token_id_bytes = borsh_len(&token_id);
+        let account_id_bytes = borsh_len(&to_account_id);
+        let u64_bytes = borsh_len(&0u64);
+
+        let metadata_bytes = borsh_len(&TokenMetadata {
+            extra: Some(certification_metadata.to_json()),
+            ..token_metadata
+        });
+
+        // Owner: tree map value and node, linked to a parent node unless it is
+        // the first token
+        let mut bytes = (PREFIX_BYTES + 1 + token_id_bytes + account_id_bytes + RECORD_BYTES)
+            + (PREFIX_BYTES + 1 + u64_bytes)
+            + (u64_bytes + token_id_bytes + 1 + 1 + u64_bytes + RECORD_BYTES);
+        if !self.tokens.owner_by_id.is_empty() {
+            bytes += u64_bytes;
+        }
+
+        // Metadata
+        bytes += PREFIX_BYTES + token_id_bytes + metadata_bytes + RECORD_BYTES;
+
+        // Enumeration: the owner's token set, unless they already hold tokens,
+        // and its element and index entries
+        let owns_tokens = self
+            .tokens
+            .tokens_per_owner
+            .as_ref()
+            .filter(|tokens_per_owner| tokens_per_owner.contains_key(&to_account_id))
+            .is_some();
+        if !owns_tokens {
+            let token_set_bytes = (4 + TOKENS_PER_OWNER_PREFIX_BYTES)
+                + (u64_bytes + 4 + TOKENS_PER_OWNER_PREFIX_BYTES);
+            bytes += PREFIX_BYTES + account_id_bytes + token_set_bytes + RECORD_BYTES;
+        }
+        bytes += (TOKENS_PER_OWNER_PREFIX_BYTES + token_id_bytes + u64_bytes + RECORD_BYTES)
+            + (TOKENS_PER_OWNER_PREFIX_BYTES + u64_bytes + token_id_bytes + RECORD_BYTES);
+
+        // Storage accounting
+        bytes += PREFIX_BYTES
+            + token_id_bytes
+            + borsh_len(&CertificationStorage { payer_id, bytes: 0 })
+            + RECORD_BYTES;
+
+        U128(env::storage_byte_cost() * bytes as u128)
+    }
+}
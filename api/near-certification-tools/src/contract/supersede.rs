@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+
+use near_contract_standards::non_fungible_token::{metadata::TokenMetadata, Token, TokenId};
+use near_sdk::{near_bindgen, serde_json};
+
+use crate::{
+    contract::*,
+    event::{CertificationEventLogData, CreateEventLog},
+    metadata::CertificationExtraMetadata,
+    utils::assert_nonzero_deposit,
+};
+
+const SUPERSEDED_REASON: &str = "superseded";
+
+#[near_bindgen]
+impl CertificationContract {
+    pub(crate) fn certification_metadata(
+        &self,
+        token_id: &TokenId,
+    ) -> Option<CertificationExtraMetadata> {
+        self.tokens
+            .token_metadata_by_id
+            .as_ref()
+            .unwrap()
+            .get(token_id)
+            .and_then(|metadata| metadata.extra)
+            .map(|extra| serde_json::from_str(&extra).unwrap())
+    }
+
+    /// Returns the IDs of all certifications linked to a particular token by
+    /// supersession, from the original to the latest replacement. The token ID
+    /// of a deleted certification can be reused, which may link the chain into
+    /// a cycle, so the walk stops at the first token it has already visited.
+    pub fn cert_supersession_chain(&self, token_id: TokenId) -> Vec<TokenId> {
+        let mut visited = HashSet::from([token_id.clone()]);
+        let mut chain = vec![];
+
+        let mut previous = self
+            .certification_metadata(&token_id)
+            .and_then(|metadata| metadata.supersedes);
+        while let Some(previous_token_id) = previous {
+            if !visited.insert(previous_token_id.clone()) {
+                break;
+            }
+            previous = self
+                .certification_metadata(&previous_token_id)
+                .and_then(|metadata| metadata.supersedes);
+            chain.insert(0, previous_token_id);
+        }
+
+        let mut next = self
+            .certification_metadata(&token_id)
+            .and_then(|metadata| metadata.superseded_by);
+        chain.push(token_id);
+        while let Some(next_token_id) = next {
+            if !visited.insert(next_token_id.clone()) {
+                break;
+            }
+            next = self
+                .certification_metadata(&next_token_id)
+                .and_then(|metadata| metadata.superseded_by);
+            chain.push(next_token_id);
+        }
+
+        chain
+    }
+
+    #[payable]
+    pub fn cert_supersede(
+        &mut self,
+        old_token_id: TokenId,
+        token_id: TokenId,
+        receiver_account_id: Option<AccountId>,
+        token_metadata: TokenMetadata,
+        certification_metadata: CertificationExtraMetadata,
+        memo: Option<String>,
+    ) -> Token {
+        self.assert_can_invalidate();
+        // Force owner only
+        self.ownership.require_owner();
+        // Force verification
+        assert_nonzero_deposit();
+
+        let lookup = self.tokens.token_metadata_by_id.as_mut().unwrap();
+        let old_metadata = lookup.get(&old_token_id).expect("Token does not exist");
+        let old_certification_metadata = serde_json::from_str::<CertificationExtraMetadata>(
+            old_metadata.extra.as_ref().unwrap(),
+        )
+        .unwrap();
+
+        require!(
+            old_certification_metadata.superseded_by.is_none(),
+            "Certification already superseded"
+        );
+
+        let was_valid = old_certification_metadata.valid;
+
+        lookup.insert(
+            &old_token_id,
+            &TokenMetadata {
+                extra: Some(
+                    CertificationExtraMetadata {
+                        superseded_by: Some(token_id.clone()),
+                        ..old_certification_metadata
+                    }
+                    .to_json(),
+                ),
+                ..old_metadata
+            },
+        );
+
+        // Replacement goes to the holder of the superseded certification by default
+        let to_account_id = receiver_account_id
+            .unwrap_or_else(|| self.tokens.owner_by_id.get(&old_token_id).unwrap());
+
+        let token = self.internal_mint_certification(
+            token_id.clone(),
+            to_account_id.clone(),
+            token_metadata,
+            CertificationExtraMetadata {
+                supersedes: Some(old_token_id.clone()),
+                superseded_by: None,
+                ..certification_metadata
+            },
+            memo.clone(),
+        );
+
+        if was_valid {
+            self.internal_invalidate(&old_token_id, Some(SUPERSEDED_REASON.to_string()));
+        }
+
+        self.create_event_log(CertificationEventLogData::Supersede {
+            recipient_id: to_account_id,
+            old_token_id,
+            token_id,
+            memo,
+        })
+        .emit();
+
+        token
+    }
+}
@@ -0,0 +1,27 @@
+use near_sdk::{json_types::Base64VecU8, near_bindgen, Gas};
+
+use crate::contract::*;
+
+const GAS_FOR_MIGRATE: Gas = Gas(50_000_000_000_000);
+
+#[near_bindgen]
+impl CertificationContract {
+    pub fn upgrade(&mut self, code: Base64VecU8, migrate: bool) -> Promise {
+        // Access control
+        let predecessor = env::predecessor_account_id();
+        require!(
+            self.ownership.owner.as_ref() == Some(&predecessor)
+                || self.rbac.has_role(&predecessor, &Role::Upgrader),
+            "Unauthorized"
+        );
+
+        let promise = Promise::new(env::current_account_id()).deploy_contract(code.into());
+
+        // Deployment is reverted if the migration fails
+        if migrate {
+            promise.function_call("migrate".to_string(), vec![], 0, GAS_FOR_MIGRATE)
+        } else {
+            promise
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeSet;
+
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::{contract::*, metadata::CertificationExtraMetadata};
+
+#[near_bindgen]
+impl CertificationContract {
+    fn valid_certifications_of(
+        &self,
+        account_id: &AccountId,
+    ) -> impl Iterator<Item = CertificationExtraMetadata> + '_ {
+        self.tokens
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(account_id))
+            .map(|token_ids| token_ids.to_vec())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|token_id| self.certification_metadata(&token_id))
+            .filter(|metadata| metadata.valid)
+    }
+
+    /// Returns `true` if a particular account holds a valid certification for
+    /// `program`, for other contracts to gate actions on.
+    pub fn cert_has_valid(&self, account_id: AccountId, program: String) -> bool {
+        self.valid_certifications_of(&account_id)
+            .any(|metadata| metadata.program.as_ref() == Some(&program))
+    }
+
+    /// Returns the programs a particular account holds valid certifications
+    /// for, sorted and without duplicates.
+    pub fn cert_valid_programs(&self, account_id: AccountId) -> Vec<String> {
+        self.valid_certifications_of(&account_id)
+            .filter_map(|metadata| metadata.program)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
@@ -0,0 +1,26 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Statement by a third party co-signing a certification.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CertificationEndorsement {
+    /// NEAR account ID of the endorsing organization.
+    pub endorser_id: AccountId,
+
+    /// Human-readable endorsement statement.
+    ///
+    /// For example: "Example Corp. recognizes this certification as
+    /// equivalent to its internal Rust training."
+    pub statement: String,
+
+    /// Endorsement timestamp, i.e. number of non-leap-nanoseconds since
+    /// January 1, 1970 0:00:00 UTC.
+    ///
+    /// Compatible with `env::block_timestamp()`.
+    pub endorsed_at: U64,
+}
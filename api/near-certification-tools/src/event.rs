@@ -1,25 +1,37 @@
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::{
+    json_types::{Base58CryptoHash, U128},
     log,
-    serde::{Deserialize, Serialize},
+    serde::Serialize,
     serde_json, AccountId,
 };
 
-use crate::CertificationContract;
+use crate::{contract::Role, CertificationContract};
 
-const EVENT_STANDARD: &'static str = "x-nearedu-cert";
-const EVENT_VERSION: &'static str = "1.0.0";
+const EVENT_STANDARD: &str = "x-nearedu-cert";
+const EVENT_VERSION: &str = "2.0.0";
 
-#[derive(Serialize, Deserialize)]
+/// NEP-297 event log, with `data` holding typed event objects in the shape of
+/// the standard `nft_mint` / `nft_burn` events.
+#[derive(Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub(crate) struct EventLogData {
-    standard: String,
-    version: String,
-    event: String,
-    data: Option<String>,
+    standard: &'static str,
+    version: &'static str,
+    event: &'static str,
+    data: Vec<CertificationEventLogData>,
 }
 
 impl EventLogData {
+    fn new(data: CertificationEventLogData) -> Self {
+        Self {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: data.name(),
+            data: vec![data],
+        }
+    }
+
     pub fn to_log(&self) -> String {
         format!("EVENT_JSON:{}", serde_json::to_string(&self).unwrap())
     }
@@ -33,8 +45,8 @@ pub(crate) trait CreateEventLog<T> {
     fn create_event_log(&self, with_data: T) -> EventLogData;
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde", untagged)]
 pub(crate) enum CertificationEventLogData {
     Issue {
         recipient_id: AccountId,
@@ -46,24 +58,192 @@ pub(crate) enum CertificationEventLogData {
         token_id: TokenId,
         memo: Option<String>,
     },
+    Endorse {
+        endorser_id: AccountId,
+        token_id: TokenId,
+        statement: String,
+    },
+    EndorsementWithdraw {
+        endorser_id: AccountId,
+        token_id: TokenId,
+    },
+    AnchorBatch {
+        issuer_id: AccountId,
+        batch_id: String,
+        merkle_root: Base58CryptoHash,
+        count: u64,
+    },
+    Supersede {
+        recipient_id: AccountId,
+        old_token_id: TokenId,
+        token_id: TokenId,
+        memo: Option<String>,
+    },
+    /// sha256 hashes of the JSON contract metadata, as returned by
+    /// `nft_metadata`
+    SetMetadata {
+        old_metadata_hash: Option<Base58CryptoHash>,
+        new_metadata_hash: Base58CryptoHash,
+    },
+    Withdraw {
+        recipient_id: AccountId,
+        amount: U128,
+    },
+    SetWithdrawalReserve {
+        reserve: U128,
+    },
+    RoleAdd {
+        role: Role,
+        account_id: AccountId,
+    },
+    RoleRemove {
+        role: Role,
+        account_id: AccountId,
+    },
+    Migrate {},
 }
 
 impl CertificationEventLogData {
-    fn name(&self) -> &str {
+    fn name(&self) -> &'static str {
         match self {
             CertificationEventLogData::Issue { .. } => "cert_issue",
             CertificationEventLogData::Invalidate { .. } => "cert_invalidate",
+            CertificationEventLogData::Endorse { .. } => "cert_endorse",
+            CertificationEventLogData::EndorsementWithdraw { .. } => "cert_endorsement_withdraw",
+            CertificationEventLogData::Supersede { .. } => "cert_supersede",
+            CertificationEventLogData::AnchorBatch { .. } => "cert_anchor_batch",
+            CertificationEventLogData::SetMetadata { .. } => "cert_set_metadata",
+            CertificationEventLogData::Withdraw { .. } => "cert_withdraw",
+            CertificationEventLogData::SetWithdrawalReserve { .. } => "cert_set_withdrawal_reserve",
+            CertificationEventLogData::RoleAdd { .. } => "cert_role_add",
+            CertificationEventLogData::RoleRemove { .. } => "cert_role_remove",
+            CertificationEventLogData::Migrate { .. } => "cert_migrate",
         }
     }
 }
 
 impl CreateEventLog<CertificationEventLogData> for CertificationContract {
     fn create_event_log(&self, with_data: CertificationEventLogData) -> EventLogData {
-        EventLogData {
-            standard: EVENT_STANDARD.to_string(),
-            version: EVENT_VERSION.to_string(),
-            event: with_data.name().to_string(),
-            data: Some(serde_json::to_string(&with_data).unwrap()),
-        }
+        EventLogData::new(with_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    fn log(data: CertificationEventLogData) -> String {
+        EventLogData::new(data).to_log()
+    }
+
+    #[test]
+    fn issue_log() {
+        assert_eq!(
+            log(CertificationEventLogData::Issue {
+                recipient_id: accounts(1),
+                token_id: "0".to_string(),
+                memo: Some("memo".to_string()),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_issue","data":[{"recipient_id":"bob","token_id":"0","memo":"memo"}]}"#,
+        );
+    }
+
+    #[test]
+    fn invalidate_log() {
+        assert_eq!(
+            log(CertificationEventLogData::Invalidate {
+                recipient_id: None,
+                token_id: "0".to_string(),
+                memo: None,
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_invalidate","data":[{"recipient_id":null,"token_id":"0","memo":null}]}"#,
+        );
+    }
+
+    #[test]
+    fn endorse_logs() {
+        assert_eq!(
+            log(CertificationEventLogData::Endorse {
+                endorser_id: accounts(2),
+                token_id: "0".to_string(),
+                statement: "Verified".to_string(),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_endorse","data":[{"endorser_id":"charlie","token_id":"0","statement":"Verified"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::EndorsementWithdraw {
+                endorser_id: accounts(2),
+                token_id: "0".to_string(),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_endorsement_withdraw","data":[{"endorser_id":"charlie","token_id":"0"}]}"#,
+        );
+    }
+
+    #[test]
+    fn anchor_batch_log() {
+        assert_eq!(
+            log(CertificationEventLogData::AnchorBatch {
+                issuer_id: accounts(3),
+                batch_id: "batch-0".to_string(),
+                merkle_root: [0; 32].into(),
+                count: 100,
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_anchor_batch","data":[{"issuer_id":"danny","batch_id":"batch-0","merkle_root":"11111111111111111111111111111111","count":100}]}"#,
+        );
+    }
+
+    #[test]
+    fn supersede_log() {
+        assert_eq!(
+            log(CertificationEventLogData::Supersede {
+                recipient_id: accounts(1),
+                old_token_id: "0".to_string(),
+                token_id: "1".to_string(),
+                memo: None,
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_supersede","data":[{"recipient_id":"bob","old_token_id":"0","token_id":"1","memo":null}]}"#,
+        );
+    }
+
+    #[test]
+    fn admin_logs() {
+        assert_eq!(
+            log(CertificationEventLogData::SetMetadata {
+                old_metadata_hash: None,
+                new_metadata_hash: [0; 32].into(),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_set_metadata","data":[{"old_metadata_hash":null,"new_metadata_hash":"11111111111111111111111111111111"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::Withdraw {
+                recipient_id: accounts(0),
+                amount: 5.into(),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_withdraw","data":[{"recipient_id":"alice","amount":"5"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::SetWithdrawalReserve { reserve: 5.into() }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_set_withdrawal_reserve","data":[{"reserve":"5"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::RoleAdd {
+                role: Role::Issuer,
+                account_id: accounts(3),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_role_add","data":[{"role":"issuer","account_id":"danny"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::RoleRemove {
+                role: Role::Endorser,
+                account_id: accounts(3),
+            }),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_role_remove","data":[{"role":"endorser","account_id":"danny"}]}"#,
+        );
+        assert_eq!(
+            log(CertificationEventLogData::Migrate {}),
+            r#"EVENT_JSON:{"standard":"x-nearedu-cert","version":"2.0.0","event":"cert_migrate","data":[{}]}"#,
+        );
     }
 }
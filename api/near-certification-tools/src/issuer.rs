@@ -0,0 +1,41 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+};
+
+/// Limits of an issuer role grant.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct IssuerGrant {
+    /// Timestamp after which the issuer can no longer mint, i.e. number of
+    /// non-leap-nanoseconds since January 1, 1970 0:00:00 UTC.
+    pub expires_at: Option<U64>,
+
+    /// Maximum number of certifications the issuer can mint.
+    pub mint_quota: Option<u64>,
+
+    /// Number of certifications minted since the grant.
+    pub minted: u64,
+}
+
+impl IssuerGrant {
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at.0)
+    }
+
+    pub fn remaining_quota(&self) -> Option<u64> {
+        self.mint_quota
+            .map(|mint_quota| mint_quota.saturating_sub(self.minted))
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct IssuerGrantView {
+    /// Expiration timestamp of the grant, `None` if it never expires.
+    pub expires_at: Option<U64>,
+
+    /// Number of certifications the issuer can still mint, `None` if
+    /// unlimited.
+    pub remaining_quota: Option<u64>,
+}
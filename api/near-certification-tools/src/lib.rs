@@ -1,11 +1,17 @@
 use near_contract_standards::non_fungible_token::TokenId;
 pub use near_sdk::AccountId;
 
-pub use crate::contract::CertificationContract;
+pub use crate::contract::{CertificationContract, CertificationContractInitOptions};
 
+mod batch;
 mod contract;
+mod endorsement;
 mod event;
+mod issuer;
+mod merkle;
 mod metadata;
+mod revocation;
+mod storage;
 mod storage_key;
 mod utils;
 
@@ -19,14 +25,23 @@ pub struct MintNFT {
     pub memo: Option<String>,
 }
 
-pub use metadata::CertificationExtraMetadata;
+pub use batch::AnchoredBatch;
+pub use endorsement::CertificationEndorsement;
+pub use issuer::IssuerGrantView;
+pub use merkle::leaf_encoding;
+pub use metadata::{
+    recipient_name_commitment_preimage, CertificationExtraMetadata, LocalizedMetadata,
+};
 pub use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+pub use revocation::CertificationRevocation;
+pub use storage::CertificationStorage;
 
 pub use near_contract_standards::non_fungible_token::Token;
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::bool_assert_comparison, clippy::useless_conversion)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     use near_contract_standards::non_fungible_token::{
         approval::NonFungibleTokenApproval,
@@ -36,13 +51,20 @@ mod tests {
     };
     use near_sdk::{
         env,
+        json_types::U128,
+        mock::VmAction,
+        serde_json,
         test_utils::{accounts, VMContextBuilder},
         testing_env, AccountId, Balance, StorageUsage,
     };
 
     use crate::{
         contract::{CertificationContract, CertificationContractInitOptions},
-        metadata::CertificationExtraMetadata,
+        endorsement::CertificationEndorsement,
+        issuer::IssuerGrantView,
+        merkle,
+        metadata::{CertificationExtraMetadata, LocalizedMetadata},
+        revocation::CertificationRevocation,
     };
 
     const MINT_MAX_COST: u128 = 20000000000000000000000;
@@ -97,8 +119,17 @@ mod tests {
             program_end_date: None,
             original_recipient_id: Some("original_recipient.near".parse().unwrap()),
             original_recipient_name: Some("Original Recipient".into()),
+            original_recipient_name_commitment: None,
             valid: true,
             memo: None,
+            grade: None,
+            score: None,
+            max_score: None,
+            level: None,
+            credits: None,
+            supersedes: None,
+            superseded_by: None,
+            localized: None,
         }
     }
 
@@ -113,8 +144,17 @@ mod tests {
             program_end_date: None,
             original_recipient_id: Some("original_recipient.near".parse().unwrap()),
             original_recipient_name: Some("Original Recipient".to_string()),
+            original_recipient_name_commitment: None,
             valid: true,
             memo: None,
+            grade: None,
+            score: None,
+            max_score: None,
+            level: None,
+            credits: None,
+            supersedes: None,
+            superseded_by: None,
+            localized: None,
         }
     }
 
@@ -168,6 +208,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
         testing_env!(context.is_view(true).build());
@@ -197,6 +238,7 @@ mod tests {
                 CertificationContractInitOptions {
                     can_transfer,
                     can_invalidate,
+                    upgrade_authority_id: None,
                 },
             );
 
@@ -213,7 +255,9 @@ mod tests {
     ) -> (VMContextBuilder, CertificationContract) {
         let context = get_context(owner_id.clone());
         testing_env!(context.build());
-        let contract = CertificationContract::new(owner_id, contract_metadata, init_options);
+        let mut contract =
+            CertificationContract::new(owner_id.clone(), contract_metadata, init_options);
+        contract.add_issuer(owner_id, None, None);
 
         (context, contract)
     }
@@ -226,6 +270,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: true,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -288,6 +333,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -335,6 +381,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: true,
+                upgrade_authority_id: None,
             },
         );
 
@@ -404,6 +451,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -443,6 +491,792 @@ mod tests {
         contract.cert_invalidate(token_id.clone(), None);
     }
 
+    #[test]
+    fn invalidate_for_account_resumable() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        let token_ids: Vec<String> = (0..3).map(|i| i.to_string()).collect();
+        for token_id in &token_ids {
+            contract.nft_mint(
+                token_id.clone(),
+                Some(accounts(1)),
+                sample_metadata_token(),
+                sample_metadata_certification_nontransferable(),
+                None,
+            );
+        }
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        let next_index = contract.cert_invalidate_for_account(accounts(1), None, None, 2);
+        assert_eq!(next_index, Some(U128(2)));
+        assert_eq!(
+            token_ids
+                .iter()
+                .filter(|token_id| contract.cert_is_valid(token_id.to_string()))
+                .count(),
+            1
+        );
+
+        let next_index = contract.cert_invalidate_for_account(accounts(1), None, next_index, 2);
+        assert_eq!(next_index, None);
+        for token_id in &token_ids {
+            assert!(!contract.cert_is_valid(token_id.clone()));
+        }
+
+        // Starting over visits the invalidated tokens without revoking them
+        // again
+        let revocation_count = contract.cert_revocation_count();
+        contract.cert_invalidate_for_account(accounts(1), None, None, 3);
+        assert_eq!(contract.cert_revocation_count(), revocation_count);
+
+        // Accounts without certifications have nothing to invalidate
+        let next_index = contract.cert_invalidate_for_account(accounts(2), None, None, 2);
+        assert_eq!(next_index, None);
+    }
+
+    #[test]
+    fn revocation_list() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        for token_id in ["0", "1", "2"] {
+            contract.nft_mint(
+                token_id.to_string(),
+                Some(accounts(1)),
+                sample_metadata_token(),
+                sample_metadata_certification_nontransferable(),
+                None,
+            );
+        }
+
+        assert_eq!(contract.cert_revocation_count().0, 0);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_timestamp(1_000)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.cert_invalidate("2".to_string(), Some("Plagiarism".to_string()));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(2_000)
+            .build());
+        contract.cert_invalidate("0".to_string(), None);
+        // Already invalid
+        contract.cert_invalidate("2".to_string(), None);
+
+        assert_eq!(contract.cert_revocation_count().0, 2);
+        assert_eq!(
+            contract.cert_revocations(None, None),
+            vec![
+                CertificationRevocation {
+                    token_id: "2".to_string(),
+                    revoked_at: 1_000.into(),
+                    reason: Some("Plagiarism".to_string()),
+                    deleted: false,
+                },
+                CertificationRevocation {
+                    token_id: "0".to_string(),
+                    revoked_at: 2_000.into(),
+                    reason: None,
+                    deleted: false,
+                },
+            ],
+        );
+
+        let page = contract.cert_revocations(Some(1.into()), Some(10));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].token_id, "0");
+        assert!(contract.cert_revocations(Some(2.into()), None).is_empty());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(3_000)
+            .build());
+        contract.cert_delete("1".to_string(), Some("Duplicate".to_string()), None);
+
+        assert_eq!(
+            contract.cert_revocations(Some(2.into()), None),
+            vec![CertificationRevocation {
+                token_id: "1".to_string(),
+                revoked_at: 3_000.into(),
+                reason: Some("Duplicate".to_string()),
+                deleted: true,
+            }],
+        );
+    }
+
+    fn mint_for_endorsement() -> (VMContextBuilder, CertificationContract) {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_endorser(accounts(2));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            Some(accounts(1)),
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+
+        (context, contract)
+    }
+
+    #[test]
+    fn endorse_and_withdraw() {
+        let (mut context, mut contract) = mint_for_endorsement();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .block_timestamp(1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.cert_endorse("0".to_string(), "Endorsed by Charlie Corp.".to_string());
+
+        assert_eq!(
+            contract.cert_endorsements("0".to_string()),
+            vec![CertificationEndorsement {
+                endorser_id: accounts(2),
+                statement: "Endorsed by Charlie Corp.".to_string(),
+                endorsed_at: 1_000.into(),
+            }],
+        );
+
+        // Endorsing again replaces the previous statement
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.cert_endorse("0".to_string(), "Updated statement".to_string());
+
+        let endorsements = contract.cert_endorsements("0".to_string());
+        assert_eq!(endorsements.len(), 1);
+        assert_eq!(endorsements[0].statement, "Updated statement");
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.cert_withdraw_endorsement("0".to_string());
+
+        assert!(contract.cert_endorsements("0".to_string()).is_empty());
+
+        // The freed storage is refunded to the endorser
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn endorse_without_role() {
+        let (mut context, mut contract) = mint_for_endorsement();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.cert_endorse("0".to_string(), "Not on the allowlist".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Endorsement does not exist")]
+    fn withdraw_endorsement_of_other_endorser() {
+        let (mut context, mut contract) = mint_for_endorsement();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.cert_endorse("0".to_string(), "Endorsed by Charlie Corp.".to_string());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.cert_withdraw_endorsement("0".to_string());
+    }
+
+    #[test]
+    fn upgrade_by_upgrade_authority() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: Some(accounts(3)),
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.upgrade(vec![0, 97, 115, 109].into(), true);
+
+        assert_eq!(near_sdk::test_utils::get_created_receipts().len(), 1);
+    }
+
+    #[test]
+    fn migrate_current_schema() {
+        let (mut context, mut contract) = mint_for_endorsement();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.cert_invalidate("0".to_string(), None);
+        env::state_write(&contract);
+
+        // State already in the current layout is kept as is
+        let contract = CertificationContract::migrate();
+
+        assert_eq!(contract.cert_revocations(None, None).len(), 1);
+        assert!(near_sdk::test_utils::get_logs()
+            .iter()
+            .all(|log| !log.contains("cert_migrate")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn upgrade_unauthorized() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: Some(accounts(3)),
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.upgrade(vec![0, 97, 115, 109].into(), false);
+    }
+
+    fn mint_as(
+        context: &mut VMContextBuilder,
+        contract: &mut CertificationContract,
+        issuer_id: AccountId,
+        token_id: &str,
+    ) {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(issuer_id)
+            .build());
+        contract.nft_mint(
+            token_id.to_string(),
+            Some(accounts(1)),
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+    }
+
+    #[test]
+    fn issuer_grant_quota() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_issuer(accounts(3), Some(1_000.into()), Some(2));
+        assert_eq!(
+            contract.get_issuer_grant(accounts(3)),
+            Some(IssuerGrantView {
+                expires_at: Some(1_000.into()),
+                remaining_quota: Some(2),
+            }),
+        );
+        assert_eq!(
+            contract.get_issuer_grant(accounts(0)),
+            Some(IssuerGrantView {
+                expires_at: None,
+                remaining_quota: None,
+            }),
+        );
+        assert_eq!(contract.get_issuer_grant(accounts(4)), None);
+
+        mint_as(&mut context, &mut contract, accounts(3), "0");
+        assert_eq!(
+            contract
+                .get_issuer_grant(accounts(3))
+                .unwrap()
+                .remaining_quota,
+            Some(1)
+        );
+        mint_as(&mut context, &mut contract, accounts(3), "1");
+        assert_eq!(
+            contract
+                .get_issuer_grant(accounts(3))
+                .unwrap()
+                .remaining_quota,
+            Some(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Issuer mint quota exhausted")]
+    fn issuer_grant_quota_exhausted() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_issuer(accounts(3), None, Some(1));
+
+        mint_as(&mut context, &mut contract, accounts(3), "0");
+        mint_as(&mut context, &mut contract, accounts(3), "1");
+    }
+
+    #[test]
+    #[should_panic(expected = "Issuer grant has expired")]
+    fn issuer_grant_expired() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_issuer(accounts(3), Some(1_000.into()), None);
+
+        context.block_timestamp(1_000);
+        mint_as(&mut context, &mut contract, accounts(3), "0");
+    }
+
+    fn certification_metadata_of(
+        contract: &CertificationContract,
+        token_id: &str,
+    ) -> CertificationExtraMetadata {
+        near_sdk::serde_json::from_str(
+            &contract
+                .nft_token(token_id.to_string())
+                .unwrap()
+                .metadata
+                .unwrap()
+                .extra
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn supersede() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        mint_as(&mut context, &mut contract, accounts(0), "0");
+
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        let token = contract.cert_supersede(
+            "0".to_string(),
+            "1".to_string(),
+            None,
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+        assert_eq!(token.owner_id, accounts(1));
+
+        let old = certification_metadata_of(&contract, "0");
+        assert!(!old.valid);
+        assert_eq!(old.superseded_by.as_deref(), Some("1"));
+
+        let new = certification_metadata_of(&contract, "1");
+        assert!(new.valid);
+        assert_eq!(new.supersedes.as_deref(), Some("0"));
+
+        assert_eq!(
+            contract.cert_revocations(None, None)[0].reason.as_deref(),
+            Some("superseded")
+        );
+
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.cert_supersede(
+            "1".to_string(),
+            "2".to_string(),
+            None,
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+
+        let chain = vec!["0".to_string(), "1".to_string(), "2".to_string()];
+        assert_eq!(contract.cert_supersession_chain("0".to_string()), chain);
+        assert_eq!(contract.cert_supersession_chain("1".to_string()), chain);
+        assert_eq!(contract.cert_supersession_chain("2".to_string()), chain);
+    }
+
+    #[test]
+    fn supersession_chain_with_reused_token_id() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        mint_as(&mut context, &mut contract, accounts(0), "0");
+
+        testing_env!(context.storage_usage(env::storage_usage()).build());
+        contract.cert_supersede(
+            "0".to_string(),
+            "1".to_string(),
+            None,
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.cert_delete("0".to_string(), None, None);
+
+        // "1" and the new "0" now supersede each other
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .build());
+        contract.cert_supersede(
+            "1".to_string(),
+            "0".to_string(),
+            None,
+            sample_metadata_token(),
+            sample_metadata_certification_nontransferable(),
+            None,
+        );
+
+        assert_eq!(
+            contract.cert_supersession_chain("1".to_string()),
+            ["0".to_string(), "1".to_string()]
+        );
+        assert_eq!(
+            contract.cert_supersession_chain("0".to_string()),
+            ["1".to_string(), "0".to_string()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Certification already superseded")]
+    fn supersede_twice() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        mint_as(&mut context, &mut contract, accounts(0), "0");
+
+        for token_id in ["1", "2"] {
+            testing_env!(context.storage_usage(env::storage_usage()).build());
+            contract.cert_supersede(
+                "0".to_string(),
+                token_id.to_string(),
+                None,
+                sample_metadata_token(),
+                sample_metadata_certification_nontransferable(),
+                None,
+            );
+        }
+    }
+
+    #[test]
+    fn verify_recipient_name() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        // borsh of `(salt, name)`: both are length-prefixed
+        let commitment: [u8; 32] = env::sha256(b"\x06\0\0\0c0ffee\x12\0\0\0Original Recipient")
+            .try_into()
+            .unwrap();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            Some(accounts(1)),
+            sample_metadata_token(),
+            CertificationExtraMetadata {
+                original_recipient_name: None,
+                original_recipient_name_commitment: Some(commitment.into()),
+                ..sample_metadata_certification_nontransferable()
+            },
+            None,
+        );
+        mint_as(&mut context, &mut contract, accounts(0), "1");
+
+        let verify = |token_id: &str, name: &str, salt: &str| {
+            contract.cert_verify_recipient_name(token_id.into(), name.into(), salt.into())
+        };
+        assert!(verify("0", "Original Recipient", "c0ffee"));
+        assert!(!verify("0", "Original Recipient", "decaf"));
+        assert!(!verify("0", "Someone Else", "c0ffee"));
+        assert!(!verify("0", "eOriginal Recipient", "c0ffe"));
+        assert!(!verify("1", "Original Recipient", "c0ffee"));
+        assert!(!verify("2", "Original Recipient", "c0ffee"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Recipient name must not be stored alongside its commitment")]
+    fn mint_recipient_name_with_commitment() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            Some(accounts(1)),
+            sample_metadata_token(),
+            CertificationExtraMetadata {
+                original_recipient_name_commitment: Some([0; 32].into()),
+                ..sample_metadata_certification_nontransferable()
+            },
+            None,
+        );
+    }
+
+    #[test]
+    fn valid_programs() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        mint_as(&mut context, &mut contract, accounts(0), "0");
+        mint_as(&mut context, &mut contract, accounts(0), "1");
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .build());
+        contract.nft_mint(
+            "2".to_string(),
+            Some(accounts(1)),
+            sample_metadata_token(),
+            sample_metadata_certification_transferable(),
+            None,
+        );
+
+        assert!(contract.cert_has_valid(accounts(1), "NTR102".to_string()));
+        assert!(contract.cert_has_valid(accounts(1), "TR101".to_string()));
+        assert!(!contract.cert_has_valid(accounts(1), "CS101".to_string()));
+        assert!(!contract.cert_has_valid(accounts(2), "NTR102".to_string()));
+        assert_eq!(
+            contract.cert_valid_programs(accounts(1)),
+            ["NTR102", "TR101"]
+        );
+        assert!(contract.cert_valid_programs(accounts(2)).is_empty());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.cert_invalidate("2".to_string(), None);
+
+        assert!(!contract.cert_has_valid(accounts(1), "TR101".to_string()));
+        assert_eq!(contract.cert_valid_programs(accounts(1)), ["NTR102"]);
+    }
+
+    #[test]
+    fn anchor_batch_and_verify_inclusion() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_issuer(accounts(3), None, Some(3));
+
+        let leaves = [
+            CertificationExtraMetadata {
+                program: Some("MOOC101".to_string()),
+                ..sample_metadata_certification_transferable()
+            },
+            CertificationExtraMetadata {
+                program: Some("MOOC101".to_string()),
+                ..sample_metadata_certification_nontransferable()
+            },
+            sample_metadata_certification_transferable(),
+        ];
+        let hashes: Vec<_> = leaves.iter().map(merkle::leaf_hash).collect();
+        let node = merkle::node_hash(&hashes[0], &hashes[1]);
+        let root = merkle::node_hash(&node, &hashes[2]);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_MAX_COST)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.cert_anchor_batch("batch-0".to_string(), root.into(), "MOOC101".to_string(), 3);
+
+        let batch = contract.cert_anchored_batch("batch-0".to_string()).unwrap();
+        assert_eq!(batch.count, 3);
+        assert_eq!(batch.issuer_id, accounts(3));
+        assert_eq!(
+            contract
+                .get_issuer_grant(accounts(3))
+                .unwrap()
+                .remaining_quota,
+            Some(0)
+        );
+
+        let [first, second, third] = leaves;
+        assert!(contract.cert_verify_inclusion(
+            "batch-0".to_string(),
+            first,
+            vec![hashes[1].into(), hashes[2].into()],
+        ));
+        assert!(!contract.cert_verify_inclusion("batch-0".to_string(), second, vec![node.into()],));
+        // The proof is valid, but the record is of another program
+        assert!(merkle::verify_inclusion(&root, hashes[2], &[node]));
+        assert!(!contract.cert_verify_inclusion("batch-0".to_string(), third, vec![node.into()],));
+        assert!(!contract.cert_verify_inclusion(
+            "batch-1".to_string(),
+            sample_metadata_certification_transferable(),
+            vec![],
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch already anchored")]
+    fn anchor_batch_twice() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        let root = merkle::leaf_hash(&sample_metadata_certification_transferable());
+
+        for _ in 0..2 {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(MINT_MAX_COST)
+                .build());
+            contract.cert_anchor_batch(
+                "batch-0".to_string(),
+                root.into(),
+                "MOOC101".to_string(),
+                1,
+            );
+        }
+    }
+
     #[test]
     fn test_approve() {
         let (mut context, mut contract) = init_contract(
@@ -451,6 +1285,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -493,6 +1328,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -542,6 +1378,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -591,6 +1428,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: true,
+                upgrade_authority_id: None,
             },
         );
 
@@ -617,7 +1455,7 @@ mod tests {
             .attached_deposit(1)
             .predecessor_account_id(accounts(0))
             .build());
-        contract.cert_delete(token_id.clone(), None);
+        contract.cert_delete(token_id.clone(), None, None);
 
         assert!(contract.nft_token(token_id.clone()).is_none());
         assert_eq!(
@@ -627,6 +1465,220 @@ mod tests {
         assert_eq!(Into::<u128>::into(contract.nft_total_supply()), 0);
     }
 
+    #[test]
+    fn delete_refunds_storage() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        contract.add_issuer(accounts(3), None, None);
+
+        // First token of the contract, second token of the same owner and
+        // first token of another owner
+        for (token_id, receiver_id) in [("0", accounts(1)), ("1", accounts(1)), ("2", accounts(2))]
+        {
+            let estimate = contract.cert_storage_cost_estimate(
+                token_id.to_string(),
+                Some(receiver_id.clone()),
+                sample_metadata_token(),
+                sample_metadata_certification_nontransferable(),
+                Some(accounts(3)),
+            );
+
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(MINT_MAX_COST)
+                .predecessor_account_id(accounts(3))
+                .build());
+            contract.nft_mint(
+                token_id.to_string(),
+                Some(receiver_id),
+                sample_metadata_token(),
+                sample_metadata_certification_nontransferable(),
+                None,
+            );
+
+            let storage = contract.cert_storage(token_id.to_string()).unwrap();
+            assert_eq!(storage.payer_id, accounts(3));
+            assert_eq!(
+                estimate.0,
+                env::storage_byte_cost() * storage.bytes as Balance
+            );
+        }
+
+        let storage = contract.cert_storage("1".to_string()).unwrap();
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.cert_delete("1".to_string(), None, None);
+
+        assert_eq!(contract.cert_storage("1".to_string()), None);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(3));
+        assert_eq!(
+            receipts[0].actions,
+            vec![VmAction::Transfer {
+                deposit: env::storage_byte_cost() * storage.bytes as Balance
+            }]
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.cert_delete("2".to_string(), None, Some(accounts(4)));
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+    }
+
+    #[test]
+    fn storage_cost_estimate_long_fields() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: true,
+                upgrade_authority_id: None,
+            },
+        );
+
+        // Account IDs of the maximum length, 64 characters
+        let long_account_id = |name: &str| -> AccountId {
+            format!("{name}.{}", "x".repeat(63 - name.len()))
+                .parse()
+                .unwrap()
+        };
+        let payer_id = long_account_id("issuer");
+        let receiver_id = long_account_id("recipient");
+        let token_id = "x".repeat(64);
+        contract.add_issuer(payer_id.clone(), None, None);
+
+        // Two-byte characters
+        let long_text = |bytes: usize| Some("é".repeat(bytes / 2));
+        let token_metadata = || TokenMetadata {
+            title: long_text(256),
+            description: long_text(4096),
+            issued_at: Some(u64::MAX.to_string()),
+            ..sample_metadata_token()
+        };
+        let certification_metadata = || CertificationExtraMetadata {
+            authority_id: Some(long_account_id("authority")),
+            authority_name: long_text(256),
+            program: long_text(64),
+            program_name: long_text(256),
+            program_link: Some(format!("https://example.com/{}", "x".repeat(2048))),
+            program_start_date: Some(u64::MAX.into()),
+            program_end_date: Some(u64::MAX.into()),
+            original_recipient_id: Some(receiver_id.clone()),
+            original_recipient_name: long_text(256),
+            memo: long_text(1024),
+            grade: long_text(64),
+            score: Some(f64::MAX),
+            max_score: Some(f64::MAX),
+            level: Some("x".repeat(64)),
+            credits: Some(f64::MAX),
+            localized: Some(BTreeMap::from(["es", "uk-UA", "vi"].map(|language| {
+                (
+                    language.to_string(),
+                    LocalizedMetadata {
+                        title: long_text(256),
+                        description: long_text(4096),
+                    },
+                )
+            }))),
+            ..sample_metadata_certification_nontransferable()
+        };
+
+        let estimate = contract.cert_storage_cost_estimate(
+            token_id.clone(),
+            Some(receiver_id.clone()),
+            token_metadata(),
+            certification_metadata(),
+            Some(payer_id.clone()),
+        );
+
+        // The estimate is enough to mint
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(estimate.0)
+            .predecessor_account_id(payer_id)
+            .build());
+        contract.nft_mint(
+            token_id.clone(),
+            Some(receiver_id.clone()),
+            token_metadata(),
+            certification_metadata(),
+            None,
+        );
+
+        let storage = contract.cert_storage(token_id).unwrap();
+        assert_eq!(
+            estimate.0,
+            env::storage_byte_cost() * storage.bytes as Balance
+        );
+    }
+
+    #[test]
+    fn admin_events() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        let event_names = || {
+            near_sdk::test_utils::get_logs()
+                .iter()
+                .map(|log| {
+                    let event: serde_json::Value =
+                        serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+                    assert_eq!(event["standard"], "x-nearedu-cert");
+                    assert_eq!(event["version"], "2.0.0");
+                    event["event"].as_str().unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.add_issuer(accounts(3), None, None);
+        contract.remove_issuer(accounts(3));
+        contract.set_metadata(NFTContractMetadata {
+            name: "Renamed".to_string(),
+            ..sample_metadata_contract()
+        });
+        contract.withdraw(1.into());
+
+        assert_eq!(
+            event_names(),
+            [
+                "cert_role_add",
+                "cert_role_remove",
+                "cert_set_metadata",
+                "cert_withdraw"
+            ],
+        );
+    }
+
     #[test]
     fn test_withdraw() {
         let (mut context, mut contract) = init_contract(
@@ -635,6 +1687,7 @@ mod tests {
             CertificationContractInitOptions {
                 can_transfer: false,
                 can_invalidate: false,
+                upgrade_authority_id: None,
             },
         );
 
@@ -686,4 +1739,116 @@ mod tests {
             "Balance should have decreased by withdrawal amount"
         );
     }
+
+    #[test]
+    fn test_withdraw_to() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let max_withdrawal = contract.get_max_withdrawal().0;
+        contract.withdraw_to(accounts(4), (max_withdrawal / 2).into());
+
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+        assert_eq!(
+            receipts[0].actions,
+            vec![VmAction::Transfer {
+                deposit: max_withdrawal / 2
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient balance")]
+    fn test_withdraw_to_above_max() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let max_withdrawal = contract.get_max_withdrawal().0;
+        contract.withdraw_to(accounts(4), (max_withdrawal + 1).into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner only")]
+    fn test_withdraw_to_by_non_owner() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.withdraw_to(accounts(4), 1.into());
+    }
+
+    #[test]
+    fn test_withdrawal_reserve() {
+        let (mut context, mut contract) = init_contract(
+            accounts(0),
+            sample_metadata_contract(),
+            CertificationContractInitOptions {
+                can_transfer: false,
+                can_invalidate: false,
+                upgrade_authority_id: None,
+            },
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let max_withdrawal_0 = contract.get_max_withdrawal().0;
+        let reserve = max_withdrawal_0 / 4;
+
+        contract.set_withdrawal_reserve(reserve.into());
+        assert_eq!(contract.get_withdrawal_reserve().0, reserve);
+        assert_eq!(contract.get_max_withdrawal().0, max_withdrawal_0 - reserve);
+
+        let balance_0 = env::account_balance();
+        contract.withdraw_max();
+        assert_eq!(
+            env::account_balance(),
+            balance_0 - (max_withdrawal_0 - reserve),
+            "Withdrawal should leave the reserve on top of the storage cost"
+        );
+        assert_eq!(contract.get_max_withdrawal().0, 0);
+
+        // The reserve may exceed the available balance
+        contract.set_withdrawal_reserve(u128::MAX.into());
+        assert_eq!(contract.get_max_withdrawal().0, 0);
+    }
 }
@@ -0,0 +1,97 @@
+//! sha256 Merkle trees of off-chain certification records.
+//!
+//! Leaves are `sha256(0x00 || version || borsh(leaf))` of a versioned,
+//! fixed-layout encoding of a `CertificationExtraMetadata` record, inner
+//! nodes are `sha256(0x01 || min(a, b) || max(a, b))`. Sorting the pair means
+//! proofs don't need to record whether a sibling is on the left or on the
+//! right. A node without a sibling is promoted to the next level unchanged.
+
+use std::collections::BTreeMap;
+
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{
+    borsh::{self, BorshSerialize},
+    env,
+    json_types::{Base58CryptoHash, U64},
+    AccountId, CryptoHash,
+};
+
+use crate::metadata::{CertificationExtraMetadata, LocalizedMetadata};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const LEAF_V1: u8 = 1;
+
+/// Leaf encoding of the batches anchored so far: the fields of
+/// `CertificationExtraMetadata` in a fixed order. Fields added to the record
+/// later are not part of it, so that the proofs of anchored batches stay
+/// valid; a new layout gets a new version.
+#[derive(BorshSerialize)]
+struct LeafV1<'a> {
+    authority_name: &'a Option<String>,
+    authority_id: &'a Option<AccountId>,
+    program: &'a Option<String>,
+    program_name: &'a Option<String>,
+    program_link: &'a Option<String>,
+    program_start_date: &'a Option<U64>,
+    program_end_date: &'a Option<U64>,
+    original_recipient_id: &'a Option<AccountId>,
+    original_recipient_name: &'a Option<String>,
+    original_recipient_name_commitment: &'a Option<Base58CryptoHash>,
+    valid: bool,
+    memo: &'a Option<String>,
+    grade: &'a Option<String>,
+    score: &'a Option<f64>,
+    max_score: &'a Option<f64>,
+    level: &'a Option<String>,
+    credits: &'a Option<f64>,
+    supersedes: &'a Option<TokenId>,
+    superseded_by: &'a Option<TokenId>,
+    localized: &'a Option<BTreeMap<String, LocalizedMetadata>>,
+}
+
+/// Bytes hashed into the Merkle leaf of a record, after the leaf prefix.
+pub fn leaf_encoding(record: &CertificationExtraMetadata) -> Vec<u8> {
+    let leaf = LeafV1 {
+        authority_name: &record.authority_name,
+        authority_id: &record.authority_id,
+        program: &record.program,
+        program_name: &record.program_name,
+        program_link: &record.program_link,
+        program_start_date: &record.program_start_date,
+        program_end_date: &record.program_end_date,
+        original_recipient_id: &record.original_recipient_id,
+        original_recipient_name: &record.original_recipient_name,
+        original_recipient_name_commitment: &record.original_recipient_name_commitment,
+        valid: record.valid,
+        memo: &record.memo,
+        grade: &record.grade,
+        score: &record.score,
+        max_score: &record.max_score,
+        level: &record.level,
+        credits: &record.credits,
+        supersedes: &record.supersedes,
+        superseded_by: &record.superseded_by,
+        localized: &record.localized,
+    };
+
+    [&[LEAF_V1][..], &leaf.try_to_vec().unwrap()].concat()
+}
+
+pub(crate) fn leaf_hash(record: &CertificationExtraMetadata) -> CryptoHash {
+    env::sha256_array(&[&[LEAF_PREFIX][..], &leaf_encoding(record)].concat())
+}
+
+pub(crate) fn node_hash(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    env::sha256_array(&[&[NODE_PREFIX], &left[..], &right[..]].concat())
+}
+
+pub(crate) fn verify_inclusion(root: &CryptoHash, leaf: CryptoHash, proof: &[CryptoHash]) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(leaf, |hash, sibling| node_hash(&hash, sibling));
+
+    &computed_root == root
+}
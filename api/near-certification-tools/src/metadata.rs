@@ -1,8 +1,13 @@
+use std::collections::BTreeMap;
+
+use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
     json_types::*,
+    require,
     serde::{Deserialize, Serialize},
-    serde_json, AccountId,
+    serde_json, AccountId, CryptoHash,
 };
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq)]
@@ -60,21 +65,150 @@ pub struct CertificationExtraMetadata {
     /// For example: "John Doe", "The Goblin Slayer", "Steve"
     pub original_recipient_name: Option<String>,
 
+    /// Commitment to the name of the original recipient,
+    /// `sha256(borsh((salt, name)))`, stored instead of `original_recipient_name` to
+    /// keep the name off-chain. The recipient keeps the salt.
+    pub original_recipient_name_commitment: Option<Base58CryptoHash>,
+
     /// Certification is valid. Freshly issued certifications have
     /// `valid == true`; invalidation sets this value to `false`.
     pub valid: bool,
 
     /// Optional additional data
     pub memo: Option<String>,
+
+    /// Human-readable grade awarded to the recipient.
+    ///
+    /// For example: "A", "1.3", "Pass"
+    pub grade: Option<String>,
+
+    /// Numeric score achieved by the recipient, between `0` and `max_score`.
+    pub score: Option<f64>,
+
+    /// Maximum achievable score. Required if `score` is set.
+    pub max_score: Option<f64>,
+
+    /// Achievement level, a lowercase identifier defined by the issuing
+    /// entity.
+    ///
+    /// For example: "pass", "merit", "distinction"
+    pub level: Option<String>,
+
+    /// Academic credits awarded by the program (e.g. ECTS credits or CEU
+    /// hours).
+    pub credits: Option<f64>,
+
+    /// ID of the certification replaced by this one.
+    pub supersedes: Option<TokenId>,
+
+    /// ID of the certification replacing this one.
+    pub superseded_by: Option<TokenId>,
+
+    /// Translations of the token metadata title and description, by BCP 47
+    /// language tag.
+    ///
+    /// For example: "es", "vi", "uk-UA"
+    pub localized: Option<BTreeMap<String, LocalizedMetadata>>,
+}
+
+/// Translation of the token metadata title and description.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LocalizedMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Checks the syntax of a BCP 47 language tag: a 2-8 letter primary language
+/// subtag followed by 1-8 character alphanumeric subtags.
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+
+    let primary_language_valid = matches!(
+        subtags.next(),
+        Some(language) if (2..=8).contains(&language.len())
+            && language.chars().all(|c| c.is_ascii_alphabetic())
+    );
+
+    primary_language_valid
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Bytes hashed into the commitment to a recipient name: the borsh encoding
+/// of `(salt, name)`, whose length prefixes keep characters from moving
+/// between the salt and the name.
+pub fn recipient_name_commitment_preimage(name: &str, salt: &str) -> Vec<u8> {
+    (salt, name).try_to_vec().unwrap()
 }
 
 impl CertificationExtraMetadata {
     pub(crate) fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
+
+    pub(crate) fn recipient_name_commitment(name: &str, salt: &str) -> CryptoHash {
+        env::sha256_array(&recipient_name_commitment_preimage(name, salt))
+    }
+
+    pub fn assert_valid(&self) {
+        require!(
+            self.original_recipient_name.is_none()
+                || self.original_recipient_name_commitment.is_none(),
+            "Recipient name must not be stored alongside its commitment"
+        );
+
+        if let Some(grade) = &self.grade {
+            require!(!grade.trim().is_empty(), "Grade must not be empty");
+        }
+
+        if let Some(max_score) = self.max_score {
+            require!(
+                max_score.is_finite() && max_score > 0.0,
+                "Maximum score must be greater than 0"
+            );
+        }
+
+        if let Some(score) = self.score {
+            require!(self.max_score.is_some(), "Score requires a maximum score");
+            let max_score = self.max_score.unwrap();
+            require!(
+                score.is_finite() && (0.0..=max_score).contains(&score),
+                "Score must be between 0 and the maximum score"
+            );
+        }
+
+        if let Some(level) = &self.level {
+            require!(
+                !level.is_empty()
+                    && level.chars().all(|c| c.is_ascii_lowercase()
+                        || c.is_ascii_digit()
+                        || c == '_'
+                        || c == '-'),
+                "Level must be a lowercase identifier"
+            );
+        }
+
+        if let Some(credits) = self.credits {
+            require!(
+                credits.is_finite() && credits >= 0.0,
+                "Credits must not be negative"
+            );
+        }
+
+        for (tag, localized) in self.localized.iter().flatten() {
+            require!(is_language_tag(tag), "Invalid BCP 47 language tag");
+            require!(
+                localized.title.is_some() || localized.description.is_some(),
+                "Localized metadata must have a title or a description"
+            );
+        }
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::explicit_auto_deref)]
 mod tests {
     use super::*;
 
@@ -89,8 +223,17 @@ mod tests {
             program_end_date: None,
             original_recipient_id: Some("original_recipient.near".parse().unwrap()),
             original_recipient_name: Some("Original Recipient".to_string()),
+            original_recipient_name_commitment: None,
             valid: true,
             memo: None,
+            grade: Some("A".to_string()),
+            score: Some(92.5),
+            max_score: Some(100.0),
+            level: Some("distinction".to_string()),
+            credits: Some(5.0),
+            supersedes: None,
+            superseded_by: None,
+            localized: None,
         }
     }
 
@@ -132,5 +275,164 @@ mod tests {
         );
         assert_eq!(deserialized.valid, true);
         assert!(deserialized.memo.as_ref().is_none());
+        assert_eq!(deserialized.grade.as_deref(), Some("A"));
+        assert_eq!(deserialized.score, Some(92.5));
+        assert_eq!(deserialized.max_score, Some(100.0));
+        assert_eq!(deserialized.level.as_deref(), Some("distinction"));
+        assert_eq!(deserialized.credits, Some(5.0));
+    }
+
+    #[test]
+    fn metadata_json_deserialization_without_achievement_fields() {
+        let json_str = r#"{
+            "authority_id": "test_authority.near",
+            "authority_name": "Test Authority",
+            "program": "PRG101",
+            "program_name": "Program Name",
+            "program_link": null,
+            "program_start_date": null,
+            "program_end_date": "1643396400000000000",
+            "original_recipient_id": "original_recipient.near",
+            "original_recipient_name": "Original Recipient",
+            "valid": true,
+            "memo": null
+        }"#;
+        let deserialized = serde_json::from_str::<CertificationExtraMetadata>(json_str).unwrap();
+        assert!(deserialized.valid);
+        assert!(deserialized.grade.is_none());
+        assert!(deserialized.score.is_none());
+        assert!(deserialized.max_score.is_none());
+        assert!(deserialized.level.is_none());
+        assert!(deserialized.credits.is_none());
+        assert!(deserialized.supersedes.is_none());
+        assert!(deserialized.superseded_by.is_none());
+        assert!(deserialized.localized.is_none());
+    }
+
+    #[test]
+    fn metadata_valid_achievement_fields() {
+        test_metadata().assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Score must be between 0 and the maximum score")]
+    fn metadata_score_above_max_score() {
+        CertificationExtraMetadata {
+            score: Some(101.0),
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Score requires a maximum score")]
+    fn metadata_score_without_max_score() {
+        CertificationExtraMetadata {
+            max_score: None,
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Level must be a lowercase identifier")]
+    fn metadata_invalid_level() {
+        CertificationExtraMetadata {
+            level: Some("With Distinction".to_string()),
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Credits must not be negative")]
+    fn metadata_negative_credits() {
+        CertificationExtraMetadata {
+            credits: Some(-1.0),
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    fn metadata_valid_localized() {
+        CertificationExtraMetadata {
+            localized: Some(BTreeMap::from([
+                (
+                    "es".to_string(),
+                    LocalizedMetadata {
+                        title: Some("Desarrollador Certificado".to_string()),
+                        description: None,
+                    },
+                ),
+                (
+                    "uk-UA".to_string(),
+                    LocalizedMetadata {
+                        title: None,
+                        description: Some("Опис".to_string()),
+                    },
+                ),
+                (
+                    "zh-Hant-TW".to_string(),
+                    LocalizedMetadata {
+                        title: Some("認證".to_string()),
+                        description: None,
+                    },
+                ),
+            ])),
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    fn language_tags() {
+        for tag in ["en", "vi", "es-419", "uk-UA", "sr-Latn-RS", "de-CH-1996"] {
+            assert!(is_language_tag(tag), "{tag}");
+        }
+        for tag in [
+            "",
+            "e",
+            "en_US",
+            "en-",
+            "-en",
+            "1en",
+            "en-toolongsubtag",
+            "en US",
+        ] {
+            assert!(!is_language_tag(tag), "{tag}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid BCP 47 language tag")]
+    fn metadata_invalid_language_tag() {
+        CertificationExtraMetadata {
+            localized: Some(BTreeMap::from([(
+                "en_US".to_string(),
+                LocalizedMetadata {
+                    title: Some("Title".to_string()),
+                    description: None,
+                },
+            )])),
+            ..test_metadata()
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Localized metadata must have a title or a description")]
+    fn metadata_empty_localized() {
+        CertificationExtraMetadata {
+            localized: Some(BTreeMap::from([(
+                "es".to_string(),
+                LocalizedMetadata {
+                    title: None,
+                    description: None,
+                },
+            )])),
+            ..test_metadata()
+        }
+        .assert_valid();
     }
 }
@@ -0,0 +1,27 @@
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U64,
+    serde::{Deserialize, Serialize},
+};
+
+/// Entry of the append-only certification revocation list, recording an
+/// invalidation or a deletion.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CertificationRevocation {
+    /// ID of the invalidated token.
+    pub token_id: TokenId,
+
+    /// Invalidation timestamp, i.e. number of non-leap-nanoseconds since
+    /// January 1, 1970 0:00:00 UTC.
+    ///
+    /// Compatible with `env::block_timestamp()`.
+    pub revoked_at: U64,
+
+    /// Reason for the invalidation, taken from the invalidation memo.
+    pub reason: Option<String>,
+
+    /// Whether the certification was deleted rather than invalidated.
+    pub deleted: bool,
+}
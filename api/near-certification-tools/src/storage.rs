@@ -0,0 +1,17 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Storage paid for a certification when it was minted.
+#[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CertificationStorage {
+    /// NEAR account ID of the account which paid for the storage, and which
+    /// is refunded when the certification is deleted.
+    pub payer_id: AccountId,
+
+    /// Number of storage bytes paid for.
+    pub bytes: u64,
+}
@@ -12,4 +12,10 @@ pub enum StorageKey {
     Approval,
     Ownership,
     Rbac,
+    Revocations,
+    Endorsements,
+    IssuerGrants,
+    AnchoredBatches,
+    TokenStorage,
+    SchemaVersion,
 }
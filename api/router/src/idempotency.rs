@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{self, Body, Full},
+    extract::State,
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+use common::{IdempotencyRecord, IdempotentRequest, Store, StoredResponse};
+use errors::APIResult;
+
+/// Handles a request sent with an `Idempotency-Key` header at most once and
+/// replays its response whenever the same request is sent with that key again.
+///
+/// Every response is stored, errors included. The key stays reserved for the
+/// request once its handler has started; if the handler is cancelled, e.g. by
+/// the timeout, the same request may run again. Handlers see the key as an
+/// `IdempotentRequest` extension, from which a mint derives its token ID, so a
+/// run after a cancelled one finds the certificate it minted instead of
+/// minting a second one.
+pub async fn idempotent(
+    State(idempotency_keys): State<Arc<Store<IdempotencyRecord>>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> APIResult<Response> {
+    let Some(key) = request.headers().get("idempotency-key") else {
+        return Ok(next.run(request).await);
+    };
+    let key = String::from_utf8_lossy(key.as_bytes()).into_owned();
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = hyper::body::to_bytes(body).await else {
+        return Err(errors::APIError::ServerError);
+    };
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map_or(parts.uri.path(), |path_and_query| path_and_query.as_str());
+    let request_hash =
+        IdempotencyRecord::request_hash(parts.method.as_str(), path_and_query, &body);
+
+    let record = IdempotencyRecord {
+        request_hash,
+        response: None,
+        interrupted: false,
+    };
+    let resumed = match idempotency_keys.try_insert(&key, record)? {
+        None => false,
+        Some(record) if record.request_hash != request_hash => {
+            return Err(errors::APIError::IdempotencyKeyMismatch { key })
+        }
+        Some(IdempotencyRecord {
+            response: Some(response),
+            ..
+        }) => return replay(response),
+        Some(_) => {
+            // Run again only if the previous run was interrupted
+            let resumed = idempotency_keys.update(&key, |record| {
+                let resumed = record.interrupted && record.response.is_none();
+                record.interrupted = false;
+                resumed
+            })?;
+            if resumed != Some(true) {
+                return Err(errors::APIError::IdempotencyKeyInUse { key });
+            }
+
+            true
+        }
+    };
+
+    let mut reservation = Reservation {
+        idempotency_keys: &idempotency_keys,
+        key: &key,
+        completed: false,
+    };
+
+    let mut request = Request::from_parts(parts, Body::from(body));
+    request.extensions_mut().insert(IdempotentRequest {
+        key: key.clone(),
+        request_hash,
+        resumed,
+    });
+    let response = next.run(request).await;
+
+    let (parts, body) = response.into_parts();
+    let Ok(body) = hyper::body::to_bytes(body).await else {
+        return Err(errors::APIError::ServerError);
+    };
+
+    let response = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(str::to_string),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+    idempotency_keys.insert(
+        &key,
+        IdempotencyRecord {
+            request_hash,
+            response: Some(response),
+            interrupted: false,
+        },
+    )?;
+    reservation.completed = true;
+
+    Ok(Response::from_parts(parts, body::boxed(Full::from(body))))
+}
+
+fn replay(response: StoredResponse) -> APIResult<Response> {
+    let mut builder = Response::builder().status(response.status);
+    if let Some(content_type) = response.content_type {
+        builder = builder.header(header::CONTENT_TYPE, content_type);
+    }
+
+    builder
+        .body(body::boxed(Full::from(response.body)))
+        .map_err(|_| errors::APIError::ServerError)
+}
+
+/// Key of a request being handled, marked as interrupted unless its response
+/// is stored.
+struct Reservation<'a> {
+    idempotency_keys: &'a Store<IdempotencyRecord>,
+    key: &'a str,
+    completed: bool,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            // A failure is already logged by the store
+            let _ = self
+                .idempotency_keys
+                .update(self.key, |record| record.interrupted = true);
+        }
+    }
+}
@@ -11,6 +11,8 @@ use errors::APIResult;
 use tower::ServiceBuilder;
 use tower_http::{timeout::TimeoutLayer, trace::TraceLayer, ServiceBuilderExt};
 
+mod idempotency;
+
 async fn authorize<B>(request: Request<B>, next: Next<B>) -> APIResult<Response> {
     let Some(x_api_key_header) = request.headers().get("x-api-key") else {
         return Err(errors::APIError::Unauthorized);
@@ -29,10 +31,10 @@ async fn authorize<B>(request: Request<B>, next: Next<B>) -> APIResult<Response>
     }
 }
 
-pub fn create_router() -> axum::Router {
+pub fn create_router(app_data: common::AppData) -> axum::Router {
     let auth_middleware = middleware::from_fn(authorize);
-
-    tracing_subscriber::fmt::init();
+    let idempotency_middleware =
+        middleware::from_fn_with_state(app_data.idempotency_keys.clone(), idempotency::idempotent);
 
     let tracing_middleware = ServiceBuilder::new()
         .sensitive_headers(once(HeaderName::from_str("x-api-key").unwrap()))
@@ -43,17 +45,1031 @@ pub fn create_router() -> axum::Router {
     axum::Router::new()
         .route(
             "/mint-cert",
-            post(mint_cert::handler).layer(auth_middleware.clone()),
+            post(mint_cert::handler)
+                .layer(idempotency_middleware.clone())
+                .layer(auth_middleware.clone()),
         )
         .route(
             "/invalidate-all-certs-for-account",
-            delete(invalidate_all_certs_for_account::handler).layer(auth_middleware.clone()),
+            delete(invalidate_all_certs_for_account::handler)
+                .layer(idempotency_middleware.clone())
+                .layer(auth_middleware.clone()),
         )
         .route(
             "/invalidate-cert",
-            delete(invalidate_cert::handler).layer(auth_middleware),
+            delete(invalidate_cert::handler)
+                .layer(idempotency_middleware.clone())
+                .layer(auth_middleware.clone()),
+        )
+        .route(
+            "/batches",
+            post(batches::create_handler).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/batches/:batch_id",
+            get(batches::handler).layer(auth_middleware.clone()),
+        )
+        .route(
+            "/batches/:batch_id/retry",
+            post(batches::retry_handler).layer(auth_middleware.clone()),
         )
+        .route("/jobs/:job_id", get(jobs::handler).layer(auth_middleware))
         .route("/cert/:token_id", get(cert::handler))
+        .route("/accounts/:account_id/certs", get(cert::account_handler))
         .layer(tracing_middleware)
-        .with_state(common::AppData::new())
+        .with_state(app_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, Request, StatusCode},
+        Router,
+    };
+    use common::{
+        AppData, ChainClient, FakeChainClient, IdempotencyRecord, Secret, SignerData, Store,
+    };
+    use near_primitives::transaction::{Action, FunctionCallAction};
+    use tower::ServiceExt;
+
+    use super::create_router;
+
+    const API_KEY: &str = "test-api-key";
+    const ISSUER_ID: &str = "issuer.test.near";
+    const RECIPIENT_ID: &str = "recipient.test.near";
+    const SALT: &str = "5c1f0e3a9b7d24c68e0f1a2b3c4d5e6f";
+
+    fn router() -> Router {
+        router_with(Arc::new(FakeChainClient::new(ISSUER_ID)), ISSUER_ID)
+    }
+
+    fn router_with(chain_client: Arc<FakeChainClient>, signer_id: &str) -> Router {
+        create_router(app_data(chain_client, signer_id))
+    }
+
+    fn app_data(chain_client: Arc<FakeChainClient>, signer_id: &str) -> AppData {
+        std::env::set_var("API_KEY", API_KEY);
+
+        AppData {
+            signer_data: SignerData {
+                account_id: signer_id.to_string(),
+                private_key: Secret::default(),
+                key_pool: vec![],
+            },
+            chain_client,
+            mint_jobs: Arc::new(Store::in_memory()),
+            mint_batches: Arc::new(Store::in_memory()),
+            minting_batches: Arc::default(),
+            idempotency_keys: Arc::new(Store::in_memory()),
+        }
+    }
+
+    fn mint_payload(recipient_id: &str, salt: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "details": {
+                "title": "Certified Rustacean",
+                "description": "Fulfilled the requirements of the Rust course.",
+                "issued_at": "1669852800000",
+                "authority_id": ISSUER_ID,
+                "authority_name": "Issuer",
+                "program": "RS101",
+                "program_name": "Rust 101",
+                "program_link": "https://near.university",
+                "program_start_date": "1667260800000",
+                "program_end_date": "1669852800000",
+                "original_recipient_id": recipient_id,
+                "original_recipient_name": "Recipient",
+                "original_recipient_name_salt": salt,
+            }
+        })
+    }
+
+    async fn send(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        api_key: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, String) {
+        let body = body.map(|body| ("application/json", body.to_string()));
+
+        send_raw(router, method, uri, api_key, body).await
+    }
+
+    async fn send_raw(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        api_key: Option<&str>,
+        body: Option<(&str, String)>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(api_key) = api_key {
+            request = request.header("x-api-key", api_key);
+        }
+        let body = match body {
+            Some((content_type, body)) => {
+                request = request.header("content-type", content_type);
+                Body::from(body)
+            }
+            None => Body::empty(),
+        };
+
+        send_request(router, request.body(body).unwrap()).await
+    }
+
+    async fn send_request(router: &Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn error_code(body: &str) -> String {
+        let error: serde_json::Value = serde_json::from_str(body).unwrap();
+
+        error["code"].as_str().unwrap().to_string()
+    }
+
+    async fn mint(router: &Router, recipient_id: &str, salt: Option<&str>) -> String {
+        let (status, body) = send(
+            router,
+            "POST",
+            "/mint-cert",
+            Some(API_KEY),
+            Some(mint_payload(recipient_id, salt)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(token["owner_id"], recipient_id);
+
+        token["token_id"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn mint_cert() {
+        let router = router();
+
+        mint(&router, RECIPIENT_ID, None).await;
+    }
+
+    #[tokio::test]
+    async fn mint_cert_requires_api_key() {
+        let router = router();
+        let payload = mint_payload(RECIPIENT_ID, None);
+
+        let (status, _) = send(&router, "POST", "/mint-cert", None, Some(payload.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(&router, "POST", "/mint-cert", Some("wrong"), Some(payload)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn mint_cert_invalid_payload() {
+        let router = router();
+        let mut payload = mint_payload(RECIPIENT_ID, None);
+        payload["details"]["authority_id"] = "Not An Account".into();
+        payload["details"]["issued_at"] = "yesterday".into();
+        payload["details"]["program_link"] = "near.university".into();
+        payload["details"]["program_start_date"] = "2022-11-01T00:00:00Z".into();
+        payload["details"]["program_end_date"] = "2022-10-01T00:00:00Z".into();
+        payload["details"]["original_recipient_name_salt"] = "salt".into();
+
+        let (status, body) =
+            send(&router, "POST", "/mint-cert", Some(API_KEY), Some(payload)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(error["code"], "invalid_payload");
+        let fields: Vec<_> = error["details"]["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "authority_id",
+                "issued_at",
+                "program_link",
+                "program_end_date",
+                "original_recipient_name_salt"
+            ],
+            "{body}"
+        );
+    }
+
+    #[tokio::test]
+    async fn mint_cert_rfc_3339_dates() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let mut payload = mint_payload(RECIPIENT_ID, None);
+        payload["details"]["issued_at"] = "2022-12-01T00:00:00Z".into();
+        payload["details"]["program_start_date"] = "2022-11-01T00:00:00Z".into();
+        payload["details"]["program_end_date"] = "2022-12-01T01:00:00+01:00".into();
+
+        let (status, body) =
+            send(&router, "POST", "/mint-cert", Some(API_KEY), Some(payload)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(token["metadata"]["issued_at"], "1669852800000");
+        let extra: serde_json::Value =
+            serde_json::from_str(token["metadata"]["extra"].as_str().unwrap()).unwrap();
+        assert_eq!(extra["program_start_date"], "1667260800000");
+        assert_eq!(extra["program_end_date"], "1669852800000");
+    }
+
+    async fn start_mint_job(router: &Router) -> String {
+        let (status, body) = send(
+            router,
+            "POST",
+            "/mint-cert?async=true",
+            Some(API_KEY),
+            Some(mint_payload(RECIPIENT_ID, None)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+
+        let job: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(job["status"], "pending");
+        assert!(job["transaction_hash"].is_string());
+
+        job["job_id"].as_str().unwrap().to_string()
+    }
+
+    async fn get_job(router: &Router, job_id: &str) -> serde_json::Value {
+        let (status, body) = send(
+            router,
+            "GET",
+            &format!("/jobs/{job_id}"),
+            Some(API_KEY),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn mint_cert_async() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let job_id = start_mint_job(&router).await;
+
+        let (status, _) = send(&router, "GET", &format!("/jobs/{job_id}"), None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        assert_eq!(get_job(&router, &job_id).await["status"], "pending");
+
+        chain_client.process_transactions();
+
+        let job = get_job(&router, &job_id).await;
+        assert_eq!(job["status"], "succeeded");
+        assert_eq!(job["token"]["owner_id"], RECIPIENT_ID);
+        // The outcome is kept once known
+        assert_eq!(get_job(&router, &job_id).await, job);
+    }
+
+    #[tokio::test]
+    async fn mint_cert_async_failure() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), "stranger.test.near");
+        let job_id = start_mint_job(&router).await;
+
+        chain_client.process_transactions();
+
+        let job = get_job(&router, &job_id).await;
+        assert_eq!(job["status"], "failed");
+        assert!(job["message"].is_string());
+    }
+
+    #[tokio::test]
+    async fn mint_cert_async_expired() {
+        let data = app_data(Arc::new(FakeChainClient::new(ISSUER_ID)), ISSUER_ID);
+        let mint_jobs = data.mint_jobs.clone();
+        let router = create_router(data);
+        let job_id = start_mint_job(&router).await;
+
+        // The transaction is never processed, i.e. stays unknown
+        let mut job = mint_jobs.get(&job_id).unwrap();
+        job.created_at -= 60 * 60 * 1000;
+        mint_jobs.insert(&job_id, job).unwrap();
+
+        assert_eq!(get_job(&router, &job_id).await["status"], "expired");
+    }
+
+    #[tokio::test]
+    async fn unknown_job() {
+        let router = router();
+
+        let (status, body) = send(&router, "GET", "/jobs/unknown", Some(API_KEY), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error_code(&body), "job_not_found");
+    }
+
+    #[tokio::test]
+    async fn invalidate_cert() {
+        let router = router();
+        let token_id = mint(&router, RECIPIENT_ID, None).await;
+
+        let (status, _) = send(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            None,
+            Some(serde_json::json!({ "token_id": token_id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = send(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            Some(API_KEY),
+            Some(serde_json::json!({ "token_id": token_id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let (status, body) = send(&router, "GET", &format!("/cert/{token_id}"), None, None).await;
+        assert_eq!(status, StatusCode::GONE);
+        assert_eq!(error_code(&body), "certificate_invalid");
+    }
+
+    #[tokio::test]
+    async fn invalidate_cert_unknown_token() {
+        let router = router();
+
+        let (status, body) = send(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            Some(API_KEY),
+            Some(serde_json::json!({ "token_id": "unknown" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error_code(&body), "token_not_found");
+        assert!(body.contains("unknown"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn invalidate_all_certs_for_account() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let token_ids = [
+            mint(&router, RECIPIENT_ID, None).await,
+            mint(&router, RECIPIENT_ID, None).await,
+        ];
+        let other_token_id = mint(&router, "other.test.near", None).await;
+
+        let (status, _) = send(
+            &router,
+            "DELETE",
+            "/invalidate-all-certs-for-account",
+            None,
+            Some(serde_json::json!({ "account_id": RECIPIENT_ID })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = send(
+            &router,
+            "DELETE",
+            "/invalidate-all-certs-for-account",
+            Some(API_KEY),
+            Some(serde_json::json!({ "account_id": RECIPIENT_ID })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        for token_id in token_ids {
+            let (status, _) = send(&router, "GET", &format!("/cert/{token_id}"), None, None).await;
+            assert_eq!(status, StatusCode::GONE);
+        }
+        // Fetched from the contract, as a valid certificate would make the
+        // route query the explorer database for its expiration
+        let other_token = common::get_token(chain_client.as_ref(), &other_token_id)
+            .await
+            .unwrap();
+        let extra: serde_json::Value =
+            serde_json::from_str(&other_token.metadata.unwrap().extra.unwrap()).unwrap();
+        assert_eq!(extra["valid"], true);
+    }
+
+    #[tokio::test]
+    async fn cert_unknown_token() {
+        let router = router();
+
+        let (status, body) = send(&router, "GET", "/cert/unknown", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error_code(&body), "token_not_found");
+    }
+
+    #[tokio::test]
+    async fn cert_recipient_name_mismatch() {
+        let router = router();
+        let token_id = mint(&router, RECIPIENT_ID, Some(SALT)).await;
+
+        let (status, body) = send(
+            &router,
+            "GET",
+            &format!("/cert/{token_id}?name=Someone%20Else&salt={SALT}"),
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error_code(&body), "recipient_name_mismatch");
+    }
+
+    /// Issued today, so the expiration is computed without the explorer
+    /// database.
+    fn issued_today() -> String {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+            .to_string()
+    }
+
+    async fn get_image(router: &Router, uri: &str) -> (StatusCode, String, Vec<u8>) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let content_type = response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+        (status, content_type, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn cert_image() {
+        let router = router();
+        let mut payload = mint_payload(RECIPIENT_ID, None);
+        payload["details"]["issued_at"] = issued_today().into();
+        payload["details"]["program"] = "Developer".into();
+        let (status, body) =
+            send(&router, "POST", "/mint-cert", Some(API_KEY), Some(payload)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let token_id = token["token_id"].as_str().unwrap();
+
+        let (status, content_type, svg) =
+            get_image(&router, &format!("/cert/{token_id}.svg")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/svg+xml");
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"), "{svg}");
+        // Text is drawn as paths, so the image doesn't need the fonts
+        assert!(!svg.contains("<text"));
+
+        let (status, content_type, png) =
+            get_image(&router, &format!("/cert/{token_id}.png")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "image/png");
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let (status, body) = send(&router, "GET", &format!("/cert/{token_id}"), None, None).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let cert: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(cert["token_id"], token_id);
+    }
+
+    fn batch_rows(count: usize) -> Vec<serde_json::Value> {
+        (0..count)
+            .map(|index| {
+                mint_payload(&format!("recipient-{index}.test.near"), None)["details"].clone()
+            })
+            .collect()
+    }
+
+    async fn wait_for_batch(router: &Router, batch_id: &str) -> serde_json::Value {
+        loop {
+            let (status, body) = send(
+                router,
+                "GET",
+                &format!("/batches/{batch_id}"),
+                Some(API_KEY),
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+
+            let batch: serde_json::Value = serde_json::from_str(&body).unwrap();
+            if batch["pending"] == 0 {
+                return batch;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    async fn start_batch(router: &Router, content_type: &str, body: String) -> String {
+        let (status, body) = send_raw(
+            router,
+            "POST",
+            "/batches",
+            Some(API_KEY),
+            Some((content_type, body)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+
+        let batch: serde_json::Value = serde_json::from_str(&body).unwrap();
+        batch["batch_id"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn batch_mint() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let rows = batch_rows(12);
+
+        let (status, _) = send(&router, "POST", "/batches", None, Some(rows.clone().into())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let batch_id = start_batch(
+            &router,
+            "application/json",
+            serde_json::to_string(&rows).unwrap(),
+        )
+        .await;
+        let batch = wait_for_batch(&router, &batch_id).await;
+        assert_eq!(batch["succeeded"], 12, "{batch}");
+
+        for row in batch["rows"].as_array().unwrap() {
+            let token = common::get_token(chain_client.as_ref(), row["token_id"].as_str().unwrap())
+                .await
+                .unwrap();
+            assert_eq!(token.owner_id.as_str(), row["recipient_id"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_mint_csv() {
+        let router = router();
+        let csv = "title,description,issued_at,authority_id,authority_name,program,program_name,\
+            program_link,program_start_date,program_end_date,original_recipient_id,\
+            original_recipient_name,score,max_score\n\
+            Rustacean,Rust course,1669852800000,issuer.test.near,Issuer,RS101,Rust 101,\
+            https://near.university,1667260800000,1669852800000,recipient.test.near,Recipient,,\n\
+            Rustacean,Rust course,1669852800000,issuer.test.near,Issuer,RS101,Rust 101,\
+            https://near.university,1667260800000,1669852800000,other.test.near,Other,90,100\n";
+
+        let batch_id = start_batch(&router, "text/csv", csv.to_string()).await;
+        let batch = wait_for_batch(&router, &batch_id).await;
+        assert_eq!(batch["succeeded"], 2, "{batch}");
+
+        let (status, report) = send(
+            &router,
+            "GET",
+            &format!("/batches/{batch_id}?format=csv"),
+            Some(API_KEY),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[0], "row,token_id,recipient_id,status,message");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].starts_with("2,"), "{report}");
+        assert!(
+            lines[2].ends_with(",other.test.near,succeeded,"),
+            "{report}"
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_mint_rejects_invalid_rows() {
+        let router = router();
+        let mut rows = batch_rows(3);
+        rows[1]["original_recipient_id"] = "Not An Account".into();
+        rows[2]["program_end_date"] = "soon".into();
+
+        let (status, body) = send(
+            &router,
+            "POST",
+            "/batches",
+            Some(API_KEY),
+            Some(rows.into()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("row 2: original_recipient_id"), "{body}");
+        assert!(body.contains("row 3: program_end_date"), "{body}");
+
+        let (status, body) = send_raw(
+            &router,
+            "POST",
+            "/batches",
+            Some(API_KEY),
+            Some((
+                "text/csv",
+                "title,description\nRustacean,Rust course\n".to_string(),
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("row 1:"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn batch_mint_retry() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let mut rows = batch_rows(5);
+        // Rejected by the contract, failing only its own row
+        rows[3]["score"] = 150.into();
+        rows[3]["max_score"] = 100.into();
+
+        let batch_id = start_batch(
+            &router,
+            "application/json",
+            serde_json::to_string(&rows).unwrap(),
+        )
+        .await;
+        let batch = wait_for_batch(&router, &batch_id).await;
+        assert_eq!(batch["succeeded"], 4, "{batch}");
+        assert_eq!(batch["rows"][3]["status"], "failed");
+        assert!(
+            batch["rows"][3]["message"]
+                .as_str()
+                .unwrap()
+                .contains("Score"),
+            "{batch}"
+        );
+
+        let (status, body) = send(
+            &router,
+            "POST",
+            &format!("/batches/{batch_id}/retry"),
+            Some(API_KEY),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+
+        // Minted rows are left alone, so no certificate is minted twice
+        let batch = wait_for_batch(&router, &batch_id).await;
+        assert_eq!(batch["succeeded"], 4, "{batch}");
+        assert_eq!(batch["failed"], 1, "{batch}");
+        for (index, row) in batch["rows"].as_array().unwrap().iter().enumerate() {
+            let recipient_id = format!("recipient-{index}.test.near");
+            let tokens = common::get_tokens_for_owner(chain_client.as_ref(), &recipient_id)
+                .await
+                .unwrap();
+            let expected = usize::from(row["status"] == "succeeded");
+            assert_eq!(tokens.len(), expected, "{recipient_id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_mint_retry_in_progress() {
+        let data = app_data(Arc::new(FakeChainClient::new(ISSUER_ID)), ISSUER_ID);
+        let minting_batches = data.minting_batches.clone();
+        let router = create_router(data);
+
+        let batch_id = start_batch(
+            &router,
+            "application/json",
+            serde_json::to_string(&batch_rows(2)).unwrap(),
+        )
+        .await;
+        wait_for_batch(&router, &batch_id).await;
+
+        // Another run is still minting the batch
+        let guard = minting_batches.start(&batch_id).unwrap();
+        let uri = format!("/batches/{batch_id}/retry");
+        let (status, body) = send(&router, "POST", &uri, Some(API_KEY), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error_code(&body), "batch_in_progress");
+
+        drop(guard);
+        let (status, body) = send(&router, "POST", &uri, Some(API_KEY), None).await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+    }
+
+    #[tokio::test]
+    async fn unknown_batch() {
+        let router = router();
+
+        let (status, body) = send(&router, "GET", "/batches/unknown", Some(API_KEY), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error_code(&body), "batch_not_found");
+    }
+
+    async fn send_idempotent(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        idempotency_key: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", API_KEY)
+            .header("idempotency-key", idempotency_key)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        send_request(router, request).await
+    }
+
+    #[tokio::test]
+    async fn mint_cert_idempotency_key() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let router = router_with(chain_client.clone(), ISSUER_ID);
+        let payload = mint_payload(RECIPIENT_ID, None);
+
+        let (status, body) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        // A retry gets the same certificate instead of minting another one
+        let (status, replayed) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replayed, body);
+        let tokens = common::get_tokens_for_owner(chain_client.as_ref(), RECIPIENT_ID)
+            .await
+            .unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let (status, _) =
+            send_idempotent(&router, "POST", "/mint-cert", "other key", payload).await;
+        assert_eq!(status, StatusCode::OK);
+        let tokens = common::get_tokens_for_owner(chain_client.as_ref(), RECIPIENT_ID)
+            .await
+            .unwrap();
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_reused_for_another_request() {
+        let router = router();
+        let token_id = mint(&router, RECIPIENT_ID, None).await;
+
+        let (status, body) = send_idempotent(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            "key",
+            serde_json::json!({ "token_id": token_id }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let (status, body) = send_idempotent(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            "key",
+            serde_json::json!({ "token_id": "other" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error_code(&body), "idempotency_key_mismatch");
+
+        let (status, _) = send_idempotent(
+            &router,
+            "POST",
+            "/mint-cert",
+            "key",
+            mint_payload(RECIPIENT_ID, None),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_replays_failure() {
+        let mut app_data = app_data(
+            Arc::new(FakeChainClient::new(ISSUER_ID)),
+            "stranger.test.near",
+        );
+        let payload = mint_payload(RECIPIENT_ID, None);
+
+        let router = create_router(app_data.clone());
+        let (status, body) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        // Even once the issuer is fixed, the key gets the same response
+        app_data.signer_data.account_id = ISSUER_ID.to_string();
+        let router = create_router(app_data);
+        let (status, replayed) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(replayed, body);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_after_interruption() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let app_data = app_data(chain_client.clone(), ISSUER_ID);
+        let idempotency_keys = app_data.idempotency_keys.clone();
+        let router = create_router(app_data);
+        let payload = mint_payload(RECIPIENT_ID, None);
+
+        let (status, minted) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Still being handled
+        idempotency_keys
+            .update("key", |record| record.response = None)
+            .unwrap();
+        let (status, body) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error_code(&body), "idempotency_key_in_use");
+
+        // Cancelled, e.g. by the timeout, after the certificate was minted:
+        // the request runs again and finds the certificate
+        idempotency_keys
+            .update("key", |record| record.interrupted = true)
+            .unwrap();
+        let (status, body) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body, minted);
+
+        // Replayed from now on
+        let (status, body) =
+            send_idempotent(&router, "POST", "/mint-cert", "key", payload.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, minted);
+        let tokens = common::get_tokens_for_owner(chain_client.as_ref(), RECIPIENT_ID)
+            .await
+            .unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        // Cancelled before the certificate was minted: the request mints it
+        idempotency_keys
+            .insert(
+                "other",
+                IdempotencyRecord {
+                    request_hash: IdempotencyRecord::request_hash(
+                        "POST",
+                        "/mint-cert",
+                        payload.to_string().as_bytes(),
+                    ),
+                    response: None,
+                    interrupted: true,
+                },
+            )
+            .unwrap();
+        let (status, body) = send_idempotent(&router, "POST", "/mint-cert", "other", payload).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_ne!(body, minted);
+        let tokens = common::get_tokens_for_owner(chain_client.as_ref(), RECIPIENT_ID)
+            .await
+            .unwrap();
+        assert_eq!(tokens.len(), 2);
+    }
+
+    async fn list_certs(router: &Router, uri: &str) -> Vec<serde_json::Value> {
+        let (status, body) = send(router, "GET", uri, None, None).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn account_certs() {
+        let router = router();
+        let issued_at = issued_today();
+
+        // More than a page of `nft_tokens_for_owner`
+        let mut token_ids = vec![];
+        for index in 0..common::TOKENS_PAGE_SIZE + 2 {
+            let mut payload = mint_payload(RECIPIENT_ID, None);
+            payload["details"]["issued_at"] = issued_at.clone().into();
+            if index % 2 == 1 {
+                payload["details"]["program"] = "RS102".into();
+            }
+
+            let (status, body) =
+                send(&router, "POST", "/mint-cert", Some(API_KEY), Some(payload)).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+            token_ids.push(token["token_id"].as_str().unwrap().to_string());
+        }
+
+        let (status, body) = send(
+            &router,
+            "DELETE",
+            "/invalidate-cert",
+            Some(API_KEY),
+            Some(serde_json::json!({ "token_id": token_ids[0] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        let certs = list_certs(&router, &format!("/accounts/{RECIPIENT_ID}/certs")).await;
+        let listed_ids: Vec<_> = certs
+            .iter()
+            .map(|cert| cert["token_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(listed_ids.len(), token_ids.len());
+        for token_id in &token_ids {
+            assert!(listed_ids.contains(&token_id.as_str()), "{token_id}");
+        }
+        for cert in &certs {
+            assert_eq!(cert["valid"], cert["token_id"] != token_ids[0].as_str());
+            assert!(!cert["expiration"].as_str().unwrap().is_empty());
+        }
+
+        let certs = list_certs(
+            &router,
+            &format!("/accounts/{RECIPIENT_ID}/certs?valid_only=true"),
+        )
+        .await;
+        assert_eq!(certs.len(), token_ids.len() - 1);
+
+        let page = list_certs(
+            &router,
+            &format!("/accounts/{RECIPIENT_ID}/certs?from_index=40&limit=20"),
+        )
+        .await;
+        let page_ids: Vec<_> = page
+            .iter()
+            .map(|cert| cert["token_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(page_ids, listed_ids[40..]);
+
+        let certs = list_certs(
+            &router,
+            &format!("/accounts/{RECIPIENT_ID}/certs?program=RS102&valid_only=true"),
+        )
+        .await;
+        assert_eq!(certs.len(), token_ids.len() / 2);
+        assert!(certs.iter().all(|cert| cert["program_code"] == "RS102"));
+
+        assert!(list_certs(&router, "/accounts/other.test.near/certs")
+            .await
+            .is_empty());
+
+        let (status, body) = send(&router, "GET", "/accounts/Invalid.near/certs", None, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error_code(&body), "account_invalid");
+    }
+
+    #[tokio::test]
+    async fn account_certs_skip_malformed_issued_at() {
+        let chain_client = Arc::new(FakeChainClient::new(ISSUER_ID));
+        let app_data = app_data(chain_client.clone(), ISSUER_ID);
+        let router = create_router(app_data.clone());
+
+        let mut payload = mint_payload(RECIPIENT_ID, None);
+        payload["details"]["issued_at"] = issued_today().into();
+        let (status, body) =
+            send(&router, "POST", "/mint-cert", Some(API_KEY), Some(payload)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let token: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        // The contract takes any string, so mint around the API's validation
+        let details: mint_cert::MintPayloadDetails =
+            serde_json::from_value(mint_payload(RECIPIENT_ID, None)["details"].clone()).unwrap();
+        let mut args =
+            serde_json::to_value(details.into_mint_nft("malformed".to_string()).unwrap()).unwrap();
+        args["token_metadata"]["issued_at"] = "yesterday".into();
+        chain_client
+            .send_transaction_to_certs(
+                vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: "nft_mint".to_string(),
+                    args: args.to_string().into_bytes(),
+                    gas: 100 * common::TGAS,
+                    deposit: mint_cert::MINT_DEPOSIT,
+                })],
+                app_data.signer_data,
+            )
+            .await
+            .unwrap();
+
+        let certs = list_certs(&router, &format!("/accounts/{RECIPIENT_ID}/certs")).await;
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0]["token_id"], token["token_id"]);
+
+        let (status, body) = send(&router, "GET", "/cert/malformed", None, None).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error_code(&body), "issued_at_invalid");
+    }
 }
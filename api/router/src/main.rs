@@ -4,7 +4,9 @@ use router::create_router;
 
 #[tokio::main]
 async fn main() -> errors::APIResult<()> {
-    let router = create_router();
+    tracing_subscriber::fmt::init();
+
+    let router = create_router(common::AppData::new());
 
     let address = SocketAddr::from(([127, 0, 0, 1], 4000));
     println!("Server listening on {address}");